@@ -0,0 +1,159 @@
+//! Throughput of packing and unpacking typical records: `cargo bench -p godot-pack-core`.
+//!
+//! Fixed layouts run through their compiled plan, the `walked` variants attach an empty enum table to a field
+//! so that the same layout goes through the field by field interpreter instead.
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use godot_pack_core::{Column, PackingDescriptor, Value, RECORDS_PER_THREAD};
+
+/// A 32 bytes entity update.
+const ENTITY: &str = "<I:id I:tick f:x f:y f:z H:flags 2x d:time";
+
+/// A 4 KB block of samples, one run of 1024 floats.
+const SAMPLES: &str = "<I:count 1023f";
+
+/// The same block in big-endian order, swapped on little-endian machines.
+const SAMPLES_BE: &str = ">I:count 1023f";
+
+fn entity_values() -> Vec<Value> {
+    vec![
+        Value::Int(42),
+        Value::Int(1000),
+        Value::Float(1.5),
+        Value::Float(-2.5),
+        Value::Float(0.25),
+        Value::Int(0x0F),
+        Value::Float(12.75),
+    ]
+}
+
+fn sample_values() -> Vec<Value> {
+    let samples = (0..1023).map(|index| Value::Float(index as f64 * 0.5));
+    std::iter::once(Value::Int(1023)).chain(samples).collect()
+}
+
+fn descriptors(format: &str, enum_field: &str) -> [(&'static str, PackingDescriptor); 2] {
+    let compiled = PackingDescriptor::sequence_from(format).unwrap();
+    let mut walked = compiled.clone();
+    walked.set_enum(enum_field, []).unwrap();
+    [("compiled", compiled), ("walked", walked)]
+}
+
+fn records(c: &mut Criterion) {
+    for (record, format, enum_field, values) in [
+        ("32B", ENTITY, "flags", entity_values()),
+        ("4KB", SAMPLES, "count", sample_values()),
+        ("4KB big-endian", SAMPLES_BE, "count", sample_values()),
+    ] {
+        let mut group = c.benchmark_group(record);
+        for (variant, descriptor) in descriptors(format, enum_field) {
+            let bytes = descriptor.pack(values.clone()).unwrap();
+            group.throughput(Throughput::Bytes(bytes.len() as u64));
+            group.bench_with_input(BenchmarkId::new("pack", variant), &values, |b, values| {
+                b.iter_batched(
+                    || values.clone(),
+                    |values| descriptor.pack(black_box(values)).unwrap(),
+                    BatchSize::SmallInput,
+                )
+            });
+            group.bench_with_input(BenchmarkId::new("unpack", variant), &bytes, |b, bytes| {
+                b.iter(|| descriptor.unpack(black_box(bytes)).unwrap())
+            });
+        }
+        group.finish();
+    }
+}
+
+/// Appends the columns of a later share of the batch, as `unpack_columns_parallel` does.
+fn append(columns: &mut [Column], parts: Vec<Column>) {
+    for (column, part) in columns.iter_mut().zip(parts) {
+        match (column, part) {
+            (Column::Bool(column), Column::Bool(part)) => column.extend(part),
+            (Column::Int32(column), Column::Int32(part)) => column.extend(part),
+            (Column::Int64(column), Column::Int64(part)) => column.extend(part),
+            (Column::Float32(column), Column::Float32(part)) => column.extend(part),
+            (Column::Float64(column), Column::Float64(part)) => column.extend(part),
+            (Column::String(column), Column::String(part)) => column.extend(part),
+            _ => unreachable!("shares of a batch have the same columns"),
+        }
+    }
+}
+
+/// Batches of entity updates, record by record, as columns and as columns over several threads, the latter
+/// either pooled or spawned on every call.
+fn batches(c: &mut Criterion) {
+    let descriptor = PackingDescriptor::sequence_from(ENTITY).unwrap();
+    let record = descriptor.pack(entity_values()).unwrap();
+    let data = record.repeat(1000);
+    let mut group = c.benchmark_group("32B x 1000");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("unpack", |b| {
+        b.iter(|| {
+            for record in black_box(&data).chunks_exact(descriptor.size()) {
+                black_box(descriptor.unpack(record).unwrap());
+            }
+        })
+    });
+    group.bench_function("unpack_columns", |b| {
+        b.iter(|| descriptor.unpack_columns(black_box(&data)).unwrap())
+    });
+    group.finish();
+
+    let data = record.repeat(100_000);
+    let mut group = c.benchmark_group("32B x 100000");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    for threads in [1, 0] {
+        let name = if threads == 1 {
+            "1 thread"
+        } else {
+            "every core"
+        };
+        group.bench_with_input(
+            BenchmarkId::new("unpack_columns_parallel", name),
+            &threads,
+            |b, &threads| {
+                b.iter(|| {
+                    descriptor
+                        .unpack_columns_parallel(black_box(&data), threads)
+                        .unwrap()
+                })
+            },
+        );
+    }
+    group.finish();
+
+    // Replays are loaded batch after batch: a call spawning its own threads, as `std::thread::scope` does,
+    // against the shared pool of `unpack_columns_parallel`.
+    let data = record.repeat(4 * RECORDS_PER_THREAD);
+    let stride = descriptor.size();
+    let mut group = c.benchmark_group("32B x 16384 over 4 threads");
+    group.throughput(Throughput::Bytes(data.len() as u64));
+    group.bench_function("spawned threads", |b| {
+        b.iter(|| {
+            std::thread::scope(|scope| {
+                let workers: Vec<_> = black_box(&data)
+                    .chunks(RECORDS_PER_THREAD * stride)
+                    .map(|data| scope.spawn(|| descriptor.unpack_columns(data).unwrap()))
+                    .collect();
+                let mut parts = workers.into_iter().map(|worker| worker.join().unwrap());
+                let mut columns = parts.next().unwrap();
+                for part in parts {
+                    append(&mut columns, part);
+                }
+                columns
+            })
+        })
+    });
+    group.bench_function("pooled threads", |b| {
+        b.iter(|| {
+            descriptor
+                .unpack_columns_parallel(black_box(&data), 4)
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, records, batches);
+criterion_main!(benches);
@@ -0,0 +1,98 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Lookups of a `FormatCache`: hits found a value, misses had to create one.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Values currently cached.
+    pub size: usize,
+}
+
+/// Values built out of formats, such as parsed descriptors, keyed by their format. Beyond its capacity, if
+/// any, the least recently used value is evicted.
+#[derive(Debug)]
+pub struct FormatCache<T> {
+    /// Values along with when they were last used.
+    entries: HashMap<String, (T, u64)>,
+    /// Formats by when they were last used, the least recently used first.
+    recency: BTreeMap<u64, String>,
+    capacity: Option<usize>,
+    /// Incremented on every lookup.
+    clock: u64,
+    stats: CacheStats,
+}
+
+impl<T> FormatCache<T> {
+    pub fn with_capacity(capacity: Option<usize>) -> Self {
+        Self {
+            entries: HashMap::new(),
+            recency: BTreeMap::new(),
+            capacity,
+            clock: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Returns the value of `format`, creating it when missing. Formats `create` fails on are not cached.
+    pub fn get_or_insert_with(
+        &mut self,
+        format: &str,
+        create: impl FnOnce(&str) -> Option<T>,
+    ) -> Option<&T> {
+        self.clock += 1;
+        if let Some((_, last_used)) = self.entries.get_mut(format) {
+            self.stats.hits += 1;
+            if let Some(format) = self.recency.remove(last_used) {
+                self.recency.insert(self.clock, format);
+            }
+            *last_used = self.clock;
+        } else {
+            self.stats.misses += 1;
+            let value = create(format)?;
+            if let Some(capacity) = self.capacity {
+                self.evict(capacity.saturating_sub(1));
+            }
+            self.entries.insert(format.to_string(), (value, self.clock));
+            self.recency.insert(self.clock, format.to_string());
+        }
+        self.entries.get(format).map(|(value, _)| value)
+    }
+
+    /// Forgets every value, statistics being kept.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.recency.clear();
+    }
+
+    pub fn capacity(&self) -> Option<usize> {
+        self.capacity
+    }
+
+    /// Bounds the cache to `capacity` values, evicting the least recently used ones already beyond it.
+    pub fn set_capacity(&mut self, capacity: Option<usize>) {
+        self.capacity = capacity;
+        if let Some(capacity) = capacity {
+            self.evict(capacity);
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            size: self.entries.len(),
+            ..self.stats
+        }
+    }
+
+    /// Evicts the least recently used values until at most `count` remain.
+    fn evict(&mut self, count: usize) {
+        while self.entries.len() > count {
+            let Some((_, format)) = self.recency.pop_first() else {
+                break;
+            };
+            self.entries.remove(&format);
+            self.stats.evictions += 1;
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::descriptor::Endianness;
+use crate::scalar::{le_bytes, put_le_bytes};
+
+/// Checksum algorithms available as field codes, each one covering every byte preceding the field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Checksum {
+    /// CRC-16/CCITT-FALSE: polynomial `0x1021`, initial value `0xFFFF`, not reflected.
+    Crc16,
+    /// CRC-32 as used by zlib, PNG and Ethernet.
+    Crc32,
+    /// Adler-32 as used by zlib streams.
+    Adler32,
+}
+
+const CRC16_TABLE: [u16; 256] = {
+    let mut table = [0u16; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = (i as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+const CRC32_TABLE: [u32; 256] = {
+    let mut table = [0u32; 256];
+    let mut i = 0;
+    while i < 256 {
+        let mut crc = i as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[i] = crc;
+        i += 1;
+    }
+    table
+};
+
+impl Checksum {
+    /// Size in bytes of the stored checksum.
+    pub(crate) fn size(&self) -> usize {
+        match self {
+            Checksum::Crc16 => core::mem::size_of::<u16>(),
+            Checksum::Crc32 | Checksum::Adler32 => core::mem::size_of::<u32>(),
+        }
+    }
+
+    /// Stores at `offset` of `record` the checksum of the bytes before it. Checksums are stored once the rest
+    /// of the record is written, so that they cover its final content.
+    pub(crate) fn store(&self, record: &mut [u8], offset: usize, endianness: Endianness) {
+        let (covered, rest) = record.split_at_mut(offset);
+        let value = self.compute(covered);
+        let target = &mut rest[..self.size()];
+        match self {
+            Checksum::Crc16 => put_le_bytes(target, (value as u16).to_le_bytes(), endianness),
+            _ => put_le_bytes(target, value.to_le_bytes(), endianness),
+        }
+    }
+
+    /// Reads the checksum stored at `offset` of `record`, returning it along with the one computed over the
+    /// bytes before it.
+    pub(crate) fn load(&self, record: &[u8], offset: usize, endianness: Endianness) -> (u32, u32) {
+        let bytes = &record[offset..offset + self.size()];
+        let stored = match self {
+            Checksum::Crc16 => u16::from_le_bytes(le_bytes(bytes, endianness)) as u32,
+            _ => u32::from_le_bytes(le_bytes(bytes, endianness)),
+        };
+        (stored, self.compute(&record[..offset]))
+    }
+
+    pub(crate) fn compute(&self, bytes: &[u8]) -> u32 {
+        match self {
+            Checksum::Crc16 => {
+                let crc = bytes.iter().fold(0xFFFFu16, |crc, byte| {
+                    (crc << 8) ^ CRC16_TABLE[((crc >> 8) as u8 ^ byte) as usize]
+                });
+                crc as u32
+            }
+            Checksum::Crc32 => {
+                let crc = bytes.iter().fold(0xFFFF_FFFFu32, |crc, byte| {
+                    (crc >> 8) ^ CRC32_TABLE[(crc as u8 ^ byte) as usize]
+                });
+                !crc
+            }
+            Checksum::Adler32 => {
+                const MODULO: u32 = 65521;
+                let (mut a, mut b) = (1u32, 0u32);
+                // 5552 is the longest run that cannot overflow before reducing.
+                for chunk in bytes.chunks(5552) {
+                    for byte in chunk {
+                        a += *byte as u32;
+                        b += a;
+                    }
+                    a %= MODULO;
+                    b %= MODULO;
+                }
+                (b << 16) | a
+            }
+        }
+    }
+}
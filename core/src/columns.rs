@@ -0,0 +1,320 @@
+use crate::descriptor::{FieldDescriptior, FieldType, PackingDescriptor};
+use crate::error::Error;
+use crate::pool;
+use crate::scalar::{put_string, Scalar};
+use crate::value::Value;
+
+/// The values of one field across a batch of records, stored as the narrowest type holding all of them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    /// `?` fields.
+    Bool(Vec<bool>),
+    /// `b`, `B`, `h`, `H`, `i` and `l` fields.
+    Int32(Vec<i32>),
+    /// `I`, `L`, `q` and `Q` fields, the upper half of `Q` wrapping around.
+    Int64(Vec<i64>),
+    /// `f` fields.
+    Float32(Vec<f32>),
+    /// `d` fields.
+    Float64(Vec<f64>),
+    /// `s` and `c` fields.
+    String(Vec<String>),
+}
+
+impl Column {
+    /// Number of records in the column.
+    pub fn len(&self) -> usize {
+        match self {
+            Column::Bool(values) => values.len(),
+            Column::Int32(values) => values.len(),
+            Column::Int64(values) => values.len(),
+            Column::Float32(values) => values.len(),
+            Column::Float64(values) => values.len(),
+            Column::String(values) => values.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An empty column for the values of `scalar` fields.
+    fn of(scalar: Scalar, capacity: usize) -> Column {
+        match scalar {
+            Scalar::Bool => Column::Bool(Vec::with_capacity(capacity)),
+            Scalar::Character => Column::String(Vec::with_capacity(capacity)),
+            Scalar::I8 | Scalar::U8 | Scalar::I16 | Scalar::U16 | Scalar::I32 => {
+                Column::Int32(Vec::with_capacity(capacity))
+            }
+            Scalar::U32 | Scalar::I64 | Scalar::U64 => Column::Int64(Vec::with_capacity(capacity)),
+            Scalar::F32 => Column::Float32(Vec::with_capacity(capacity)),
+            Scalar::F64 => Column::Float64(Vec::with_capacity(capacity)),
+        }
+    }
+
+    /// Appends `value`, as read from a field of the column's type.
+    fn push(&mut self, value: Value) {
+        match (self, value) {
+            (Column::Bool(values), Value::Bool(value)) => values.push(value),
+            (Column::Int32(values), Value::Int(value)) => values.push(value as i32),
+            (Column::Int64(values), Value::Int(value)) => values.push(value),
+            (Column::Float32(values), Value::Float(value)) => values.push(value as f32),
+            (Column::Float64(values), Value::Float(value)) => values.push(value),
+            (Column::String(values), Value::String(value)) => values.push(value),
+            _ => unreachable!("fields read values of their column's type"),
+        }
+    }
+
+    /// Appends the records of `other`, a column of the same type.
+    fn append(&mut self, other: Column) {
+        match (self, other) {
+            (Column::Bool(values), Column::Bool(other)) => values.extend(other),
+            (Column::Int32(values), Column::Int32(other)) => values.extend(other),
+            (Column::Int64(values), Column::Int64(other)) => values.extend(other),
+            (Column::Float32(values), Column::Float32(other)) => values.extend(other),
+            (Column::Float64(values), Column::Float64(other)) => values.extend(other),
+            (Column::String(values), Column::String(other)) => values.extend(other),
+            _ => unreachable!("columns of the same field have the same type"),
+        }
+    }
+
+    /// Value of the record at `index`, which must be within the column.
+    pub fn value(&self, index: usize) -> Value {
+        match self {
+            Column::Bool(values) => Value::Bool(values[index]),
+            Column::Int32(values) => Value::Int(values[index] as i64),
+            Column::Int64(values) => Value::Int(values[index]),
+            Column::Float32(values) => Value::Float(values[index] as f64),
+            Column::Float64(values) => Value::Float(values[index]),
+            Column::String(values) => Value::String(values[index].clone()),
+        }
+    }
+}
+
+impl PackingDescriptor {
+    /// Unpacks a batch of back to back records into one column per value field, in field order. Records need
+    /// a fixed layout, without unions or conditions. Enum fields stay numbers, checked against their table
+    /// when strict.
+    pub fn unpack_columns(&self, data: &[u8]) -> Result<Vec<Column>, Error> {
+        self.unpack_columns_parallel(data, 1)
+    }
+
+    /// Unpacks a batch as `unpack_columns` does, splitting it over up to `threads` threads (all available
+    /// cores when zero). Batches of less than `RECORDS_PER_THREAD` records per thread use fewer threads.
+    /// Threads are taken from a pool started on first use and reused by later calls.
+    pub fn unpack_columns_parallel(
+        &self,
+        data: &[u8],
+        threads: usize,
+    ) -> Result<Vec<Column>, Error> {
+        let layout = self.fixed_layout()?;
+        let stride = self.size();
+        if !data.len().is_multiple_of(stride) {
+            return Err(Error::Truncated(data.len().next_multiple_of(stride)));
+        }
+        let chunk = records_per_thread(data.len() / stride, threads) * stride;
+        if data.len() <= chunk {
+            return self.decode_columns(&layout, data, 0);
+        }
+        let layout = &layout;
+        let tasks = data
+            .chunks(chunk)
+            .enumerate()
+            .map(|(index, data)| -> Box<dyn FnOnce() -> _ + Send + '_> {
+                Box::new(move || self.decode_columns(layout, data, index * chunk / stride))
+            })
+            .collect();
+        let mut results = pool::run(tasks).into_iter();
+        let mut columns = results.next().unwrap_or_else(|| Ok(vec![]))?;
+        for result in results {
+            for (column, part) in columns.iter_mut().zip(result?) {
+                column.append(part);
+            }
+        }
+        Ok(columns)
+    }
+
+    /// Decodes the columns of whole records, `first` being the index of the first one within the batch.
+    fn decode_columns(
+        &self,
+        layout: &[(usize, &FieldDescriptior)],
+        data: &[u8],
+        first: usize,
+    ) -> Result<Vec<Column>, Error> {
+        let stride = self.size();
+        let endianness = *self.endianness();
+        let mut columns = vec![];
+        for &(offset, field) in layout {
+            let fields = data
+                .chunks_exact(stride)
+                .map(|record| &record[offset..offset + field.length]);
+            let column = match &field.ty {
+                FieldType::Padding => continue,
+                FieldType::Checksum(checksum) => {
+                    for (index, record) in data.chunks_exact(stride).enumerate() {
+                        let (stored, expected) = checksum.load(record, offset, endianness);
+                        if stored != expected {
+                            return Err(Error::Unpack(format!(
+                                "Checksum mismatch in record {}: stored {}, computed {}.",
+                                first + index,
+                                stored,
+                                expected
+                            )));
+                        }
+                    }
+                    continue;
+                }
+                FieldType::String => {
+                    let mut strings = vec![];
+                    for (index, bytes) in fields.enumerate() {
+                        let Ok(string) = str::from_utf8(bytes) else {
+                            return Err(Error::Unpack(format!(
+                                "Invalid UTF-8 in string at offset {} of record {}.",
+                                offset,
+                                first + index
+                            )));
+                        };
+                        strings.push(string.to_string());
+                    }
+                    Column::String(strings)
+                }
+                ty => {
+                    let scalar =
+                        Scalar::of(ty).expect("fixed layouts only hold scalars and strings");
+                    let mut column = Column::of(scalar, data.len() / stride);
+                    for bytes in fields {
+                        column.push(scalar.read(bytes, endianness));
+                    }
+                    column
+                }
+            };
+            if let (Some(table), true) = (&field.enumeration, self.is_strict()) {
+                let numbers: Vec<i64> = match &column {
+                    Column::Int32(values) => values.iter().map(|&value| value as i64).collect(),
+                    Column::Int64(values) => values.clone(),
+                    _ => vec![],
+                };
+                for number in numbers {
+                    table.decode(Value::Int(number), true)?;
+                }
+            }
+            columns.push(column);
+        }
+        Ok(columns)
+    }
+
+    /// Packs a batch of records from one column per value field, in field order, every column holding a value
+    /// for each record. Values are converted as by `pack` and records need a fixed layout, as for
+    /// `unpack_columns`.
+    pub fn pack_columns(&self, columns: &[Column]) -> Result<Vec<u8>, Error> {
+        self.pack_columns_parallel(columns, 1)
+    }
+
+    /// Packs a batch as `pack_columns` does, splitting it over up to `threads` threads as
+    /// `unpack_columns_parallel` does.
+    pub fn pack_columns_parallel(
+        &self,
+        columns: &[Column],
+        threads: usize,
+    ) -> Result<Vec<u8>, Error> {
+        let layout = self.fixed_layout()?;
+        let stride = self.size();
+        let value_count = layout
+            .iter()
+            .filter(|(_, field)| field.ty.has_value())
+            .count();
+        if columns.len() != value_count {
+            return Err(Error::Pack(format!(
+                "Expected {} columns, got {}.",
+                value_count,
+                columns.len()
+            )));
+        }
+        let count = columns.first().map_or(0, Column::len);
+        if let Some(column) = columns.iter().find(|column| column.len() != count) {
+            return Err(Error::Pack(format!(
+                "Columns must have the same length, got {} and {}.",
+                count,
+                column.len()
+            )));
+        }
+        let mut output = vec![0; stride * count];
+        let chunk = records_per_thread(count, threads) * stride;
+        if output.len() <= chunk {
+            self.encode_columns(&layout, columns, 0, &mut output)?;
+            return Ok(output);
+        }
+        let layout = &layout;
+        let tasks = output
+            .chunks_mut(chunk)
+            .enumerate()
+            .map(|(index, output)| -> Box<dyn FnOnce() -> _ + Send + '_> {
+                Box::new(move || {
+                    self.encode_columns(layout, columns, index * chunk / stride, output)
+                })
+            })
+            .collect();
+        pool::run(tasks)
+            .into_iter()
+            .collect::<Result<(), Error>>()?;
+        Ok(output)
+    }
+
+    /// Encodes whole records into `output`, `first` being the index of the first one within the columns.
+    fn encode_columns(
+        &self,
+        layout: &[(usize, &FieldDescriptior)],
+        columns: &[Column],
+        first: usize,
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        let stride = self.size();
+        let endianness = *self.endianness();
+        let value_fields = layout.iter().filter(|(_, field)| field.ty.has_value());
+        for ((offset, field), column) in value_fields.zip(columns) {
+            for (index, record) in output.chunks_exact_mut(stride).enumerate() {
+                let index = first + index;
+                let target = &mut record[*offset..*offset + field.length];
+                let value = match &field.enumeration {
+                    Some(table) => table.encode(column.value(index), self.is_strict())?,
+                    None => column.value(index),
+                };
+                match &field.ty {
+                    FieldType::String => put_string(target, &value),
+                    ty => {
+                        let scalar =
+                            Scalar::of(ty).expect("fixed layouts only hold scalars and strings");
+                        if !scalar.write(&value, target, endianness) {
+                            return Err(Error::Pack(format!(
+                                "Cannot pack `{}` as {} in record {}.",
+                                value,
+                                scalar.name(),
+                                index
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        for (offset, field) in layout {
+            if let FieldType::Checksum(checksum) = field.ty {
+                for record in output.chunks_exact_mut(stride) {
+                    checksum.store(record, *offset, endianness);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Fewest records a thread is given, so that small batches are not split over threads for nothing.
+pub const RECORDS_PER_THREAD: usize = 4096;
+
+/// How many of `count` records each of up to `threads` threads is given, zero standing for every core.
+fn records_per_thread(count: usize, threads: usize) -> usize {
+    let threads = match threads {
+        0 => std::thread::available_parallelism().map_or(1, usize::from),
+        threads => threads,
+    };
+    count.div_ceil(threads).max(RECORDS_PER_THREAD)
+}
@@ -0,0 +1,746 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+
+use crate::checksum::Checksum;
+use crate::error::Error;
+use crate::parser::Parser;
+use crate::plan::Plan;
+use crate::scalar::{put_string, Scalar};
+use crate::value::{Value, ValueKind};
+
+#[derive(Debug, Clone)]
+pub(crate) enum FieldType {
+    String,
+    Character,
+    Bool,
+    Char,
+    UnsignedChar,
+    Short,
+    UnsignedShort,
+    Int,
+    UnsignedInt,
+    Long,
+    UnsignedLong,
+    LongLong,
+    UnsignedLongLong,
+    Float,
+    Double,
+    Padding,
+    Checksum(Checksum),
+    Union(Vec<UnionBranch>),
+    Optional(Condition),
+    Align(usize),
+    Seek(usize),
+}
+
+/// Byte order of the multi-byte fields of a record.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endianness {
+    LittleEndian,
+    BigEndian,
+}
+
+impl Endianness {
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Endianness = Self::LittleEndian;
+
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Endianness = Self::BigEndian;
+
+    pub const NETWORK: Endianness = Self::BigEndian;
+}
+
+impl FieldType {
+    /// Whether the field stands for a value, as opposed to layout only constructs.
+    pub(crate) fn has_value(&self) -> bool {
+        !matches!(
+            self,
+            FieldType::Padding
+                | FieldType::Checksum(_)
+                | FieldType::Union(_)
+                | FieldType::Optional(_)
+                | FieldType::Align(_)
+                | FieldType::Seek(_)
+        )
+    }
+
+    pub(crate) fn is_integer(&self) -> bool {
+        matches!(
+            self,
+            FieldType::Char
+                | FieldType::UnsignedChar
+                | FieldType::Short
+                | FieldType::UnsignedShort
+                | FieldType::Int
+                | FieldType::UnsignedInt
+                | FieldType::Long
+                | FieldType::UnsignedLong
+                | FieldType::LongLong
+                | FieldType::UnsignedLongLong
+        )
+    }
+
+    /// Type of the field's values.
+    fn value_kind(&self) -> ValueKind {
+        match self {
+            FieldType::String | FieldType::Character => ValueKind::String,
+            FieldType::Bool => ValueKind::Bool,
+            FieldType::Float | FieldType::Double => ValueKind::Float,
+            _ => ValueKind::Int,
+        }
+    }
+
+    /// Short human readable name of the field's type, as shown by introspection.
+    fn kind(&self) -> &'static str {
+        match self {
+            FieldType::String => "string",
+            FieldType::Character => "character",
+            FieldType::Bool => "bool",
+            FieldType::Char => "int8",
+            FieldType::UnsignedChar => "uint8",
+            FieldType::Short => "int16",
+            FieldType::UnsignedShort => "uint16",
+            FieldType::Int | FieldType::Long => "int32",
+            FieldType::UnsignedInt | FieldType::UnsignedLong => "uint32",
+            FieldType::LongLong => "int64",
+            FieldType::UnsignedLongLong => "uint64",
+            FieldType::Float => "float",
+            FieldType::Double => "double",
+            FieldType::Padding => "padding",
+            FieldType::Checksum(Checksum::Crc16) => "crc16",
+            FieldType::Checksum(Checksum::Crc32) => "crc32",
+            FieldType::Checksum(Checksum::Adler32) => "adler32",
+            FieldType::Union(_) => "union",
+            FieldType::Optional(_) => "optional",
+            FieldType::Align(_) => "align",
+            FieldType::Seek(_) => "seek",
+        }
+    }
+}
+
+/// Symbolic names attached to an integer field, in both directions.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EnumTable {
+    names: HashMap<i64, String>,
+    values: HashMap<String, i64>,
+}
+
+impl EnumTable {
+    /// Resolves either a name or a number into the number to be written.
+    pub(crate) fn encode(&self, value: Value, strict: bool) -> Result<Value, Error> {
+        match value {
+            Value::String(name) | Value::Name(name) => match self.values.get(&name) {
+                Some(number) => Ok(Value::Int(*number)),
+                None => Err(Error::Pack(format!("Unknown enum name `{}`.", name))),
+            },
+            _ => {
+                if strict {
+                    let known = value
+                        .to_i64()
+                        .is_some_and(|number| self.names.contains_key(&number));
+                    if !known {
+                        return Err(Error::Pack(format!("Unknown enum value `{}`.", value)));
+                    }
+                }
+                Ok(value)
+            }
+        }
+    }
+
+    /// Maps a decoded number to its name, leaving unknown numbers as they are unless `strict`.
+    pub(crate) fn decode(&self, value: Value, strict: bool) -> Result<Value, Error> {
+        let name = value.to_i64().and_then(|number| self.names.get(&number));
+        match name {
+            Some(name) => Ok(Value::Name(name.clone())),
+            None if strict => Err(Error::Unpack(format!("Unknown enum value `{}`.", value))),
+            None => Ok(value),
+        }
+    }
+}
+
+/// One layout of a union, chosen when the discriminant equals `key`; a `None` key is the `_` fallback.
+#[derive(Debug, Clone)]
+pub(crate) struct UnionBranch {
+    pub(crate) key: Option<i64>,
+    pub(crate) endianness: Option<Endianness>,
+    pub(crate) fields: Vec<FieldDescriptior>,
+}
+
+impl UnionBranch {
+    fn select(branches: &[UnionBranch], discriminant: Option<i64>) -> Result<&UnionBranch, String> {
+        let Some(discriminant) = discriminant else {
+            return Err("Missing discriminant for union.".to_string());
+        };
+        branches
+            .iter()
+            .find(|branch| branch.key == Some(discriminant))
+            .or_else(|| branches.iter().find(|branch| branch.key.is_none()))
+            .ok_or_else(|| format!("No union branch for discriminant {}.", discriminant))
+    }
+}
+
+/// A group of fields only present when the integer field `field` (masked by `mask`, if any) is not zero.
+#[derive(Debug, Clone)]
+pub(crate) struct Condition {
+    pub(crate) field: String,
+    pub(crate) mask: Option<i64>,
+    pub(crate) fields: Vec<FieldDescriptior>,
+}
+
+impl Condition {
+    fn holds(&self, scope: &HashMap<&str, i64>) -> bool {
+        let value = scope.get(self.field.as_str()).copied().unwrap_or(0);
+        value & self.mask.unwrap_or(-1) != 0
+    }
+
+    /// Number of values the group stands for, each one is `null` when the group is absent.
+    fn value_count(&self) -> usize {
+        self.fields
+            .iter()
+            .map(|field| match &field.ty {
+                FieldType::Optional(condition) => condition.value_count(),
+                ty => usize::from(ty.has_value()),
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct FieldDescriptior {
+    pub(crate) ty: FieldType,
+    pub(crate) length: usize,
+    pub(crate) name: Option<String>,
+    pub(crate) enumeration: Option<EnumTable>,
+}
+
+impl FieldDescriptior {
+    /// Walks the layout as the smallest possible record would, advancing `offset` and returning the furthest
+    /// byte reached, which is the minimum size once done from the start of the record.
+    fn minimum_extent(fields: &[FieldDescriptior], offset: &mut usize) -> usize {
+        let mut extent = *offset;
+        for field in fields {
+            match &field.ty {
+                FieldType::Union(branches) => {
+                    // The branch ending first is not necessarily the smallest one once it seeks, so both
+                    // minimums are taken separately, which keeps them lower bounds whatever the branch.
+                    let start = *offset;
+                    let (mut end, mut branch_extent) = (usize::MAX, usize::MAX);
+                    for branch in branches {
+                        let mut offset = start;
+                        branch_extent =
+                            branch_extent.min(Self::minimum_extent(&branch.fields, &mut offset));
+                        end = end.min(offset);
+                    }
+                    *offset = end;
+                    extent = extent.max(branch_extent);
+                }
+                FieldType::Optional(_) => {}
+                FieldType::Align(alignment) => *offset = offset.next_multiple_of(*alignment),
+                FieldType::Seek(position) => *offset = *position,
+                _ => *offset += field.length,
+            }
+            extent = extent.max(*offset);
+        }
+        extent
+    }
+
+    /// Calls `visit` on every field called `name`, looking into unions and conditions.
+    fn for_each_named(
+        fields: &mut [FieldDescriptior],
+        name: &str,
+        visit: &mut dyn FnMut(&mut FieldDescriptior),
+    ) {
+        for field in fields {
+            if field.name.as_deref() == Some(name) {
+                visit(field);
+            } else if let FieldType::Union(branches) = &mut field.ty {
+                for branch in branches {
+                    Self::for_each_named(&mut branch.fields, name, visit);
+                }
+            } else if let FieldType::Optional(condition) = &mut field.ty {
+                Self::for_each_named(&mut condition.fields, name, visit);
+            }
+        }
+    }
+
+    /// Appends the named value fields to `properties`, merging repeated names and leaving untyped those
+    /// which may be `null` or whose type differs between occurrences.
+    fn collect_properties(
+        fields: &[FieldDescriptior],
+        nullable: bool,
+        properties: &mut Vec<(String, Option<ValueKind>)>,
+    ) -> Result<(), Error> {
+        for field in fields {
+            match &field.ty {
+                FieldType::Union(branches) => {
+                    for branch in branches {
+                        Self::collect_properties(&branch.fields, nullable, properties)?;
+                    }
+                }
+                FieldType::Optional(condition) => {
+                    Self::collect_properties(&condition.fields, true, properties)?;
+                }
+                ty if ty.has_value() => {
+                    let Some(name) = &field.name else {
+                        return Err(Error::Format(
+                            "Every value field must be named.".to_string(),
+                        ));
+                    };
+                    let kind = (!nullable).then(|| ty.value_kind());
+                    match properties.iter_mut().find(|(existing, _)| existing == name) {
+                        Some((_, existing)) if *existing != kind => *existing = None,
+                        Some(_) => {}
+                        None => properties.push((name.clone(), kind)),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A parsed format, packing values into records and unpacking them back.
+#[derive(Debug, Clone)]
+pub struct PackingDescriptor {
+    fields: Vec<FieldDescriptior>,
+    size: usize,
+    endianness: Endianness,
+    strict: bool,
+    /// Compiled steps of fixed layouts without enums, the fields being walked otherwise.
+    plan: Option<Plan>,
+}
+
+impl PackingDescriptor {
+    pub fn sequence_from(seq: &str) -> Result<PackingDescriptor, Error> {
+        let (fields, order) = Parser::new(seq).sequence(&[])?;
+
+        let mut descriptor = PackingDescriptor {
+            size: FieldDescriptior::minimum_extent(&fields, &mut 0),
+            fields,
+            endianness: order.unwrap_or(Endianness::NATIVE),
+            strict: false,
+            plan: None,
+        };
+        descriptor.plan = descriptor
+            .fixed_layout()
+            .ok()
+            .and_then(|layout| Plan::compile(&layout, descriptor.endianness));
+        Ok(descriptor)
+    }
+
+    /// Size of the smallest record, the only one for layouts without unions, conditions or seeks.
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn endianness(&self) -> &Endianness {
+        &self.endianness
+    }
+
+    /// When strict, values missing from an enum table are rejected by both `pack` and `unpack`.
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Attaches an enum table, mapping names to values, to every integer field called `name`.
+    pub fn set_enum(
+        &mut self,
+        name: &str,
+        table: impl IntoIterator<Item = (String, i64)>,
+    ) -> Result<(), Error> {
+        let (mut found, mut integers) = (0, true);
+        FieldDescriptior::for_each_named(&mut self.fields, name, &mut |field| {
+            found += 1;
+            integers &= field.ty.is_integer();
+        });
+        if found == 0 {
+            return Err(Error::Format(format!("No field named `{}`.", name)));
+        }
+        if !integers {
+            return Err(Error::Format(format!(
+                "Field `{}` is not an integer, cannot attach an enum.",
+                name
+            )));
+        }
+        let mut enumeration = EnumTable::default();
+        for (name, number) in table {
+            enumeration.names.insert(number, name.clone());
+            enumeration.values.insert(name, number);
+        }
+        FieldDescriptior::for_each_named(&mut self.fields, name, &mut |field| {
+            field.enumeration = Some(enumeration.clone());
+        });
+        self.plan = None;
+        Ok(())
+    }
+
+    /// Named value fields in record order, along with their type if it is always the same. Fails if a value
+    /// field is unnamed.
+    pub fn properties(&self) -> Result<Vec<(String, Option<ValueKind>)>, Error> {
+        let mut properties = vec![];
+        FieldDescriptior::collect_properties(&self.fields, false, &mut properties)?;
+        Ok(properties)
+    }
+
+    /// Offsets of every field for layouts without unions or conditions, where all records have the same size.
+    pub(crate) fn fixed_layout(&self) -> Result<Vec<(usize, &FieldDescriptior)>, Error> {
+        if self.size == 0 {
+            return Err(Error::Format(
+                "Records must be at least one byte long.".to_string(),
+            ));
+        }
+        let mut layout = vec![];
+        let mut offset = 0;
+        for field in &self.fields {
+            match &field.ty {
+                FieldType::Union(_) | FieldType::Optional(_) => {
+                    return Err(Error::Format(
+                        "Records of a batch cannot have unions or conditions.".to_string(),
+                    ))
+                }
+                FieldType::Align(alignment) => offset = usize::next_multiple_of(offset, *alignment),
+                FieldType::Seek(position) => offset = *position,
+                _ => {
+                    layout.push((offset, field));
+                    offset += field.length;
+                }
+            }
+        }
+        Ok(layout)
+    }
+
+    /// Packs `values` in field order, missing trailing values leaving their fields zeroed.
+    pub fn pack(&self, values: impl IntoIterator<Item = Value>) -> Result<Vec<u8>, Error> {
+        self.pack_from(values.into_iter().peekable())
+    }
+
+    /// Packs the values provided by `values`, such as the properties of an object.
+    pub fn pack_from(&self, mut values: impl ValueSource) -> Result<Vec<u8>, Error> {
+        if let Some(plan) = &self.plan {
+            let mut output = vec![0; self.size];
+            plan.pack(&mut values, &mut output)?;
+            plan.write_checksums(&mut output);
+            return Ok(output);
+        }
+        let mut state = PackState {
+            values,
+            output: Vec::with_capacity(self.size),
+            offset: 0,
+            scope: HashMap::new(),
+            checksums: vec![],
+        };
+        self.pack_fields(&self.fields, &self.endianness, &mut state)?;
+        let mut output = state.output;
+        output.resize(usize::max(output.len(), self.size), 0);
+        for (checksum, offset, endianess) in state.checksums {
+            checksum.store(&mut output, offset, endianess);
+        }
+        Ok(output)
+    }
+
+    fn pack_fields<'a>(
+        &self,
+        fields: &'a [FieldDescriptior],
+        endianess: &Endianness,
+        state: &mut PackState<'a, impl ValueSource>,
+    ) -> Result<(), Error> {
+        let mut discriminant: Option<i64> = None;
+        for descriptor in fields {
+            match &descriptor.ty {
+                FieldType::Union(branches) => {
+                    let branch =
+                        UnionBranch::select(branches, discriminant).map_err(Error::Pack)?;
+                    let endianess = branch.endianness.as_ref().unwrap_or(endianess);
+                    self.pack_fields(&branch.fields, endianess, state)?;
+                    continue;
+                }
+                FieldType::Optional(condition) if condition.holds(&state.scope) => {
+                    self.pack_fields(&condition.fields, endianess, state)?;
+                    continue;
+                }
+                FieldType::Optional(condition) => {
+                    state.values.skip_absent(condition.value_count());
+                    continue;
+                }
+                FieldType::Align(alignment) => {
+                    state.move_to(state.offset.next_multiple_of(*alignment));
+                    continue;
+                }
+                FieldType::Seek(position) => {
+                    state.move_to(*position);
+                    continue;
+                }
+                _ => {}
+            }
+            let bounds = state.offset..(state.offset + descriptor.length);
+            state.move_to(bounds.end);
+            match descriptor.ty {
+                FieldType::Padding => continue,
+                FieldType::Checksum(checksum) => {
+                    state.checksums.push((checksum, bounds.start, *endianess));
+                    continue;
+                }
+                _ => {}
+            }
+            if descriptor.ty.is_integer() {
+                discriminant = None;
+            }
+            let Some(value) = state.values.next_value(descriptor.name.as_deref())? else {
+                continue;
+            };
+            let value = match &descriptor.enumeration {
+                Some(table) => table.encode(value, self.strict)?,
+                None => value,
+            };
+            if descriptor.ty.is_integer() {
+                discriminant = value.to_i64();
+                if let (Some(name), Some(value)) = (&descriptor.name, discriminant) {
+                    state.scope.insert(name, value);
+                }
+            }
+            let target = &mut state.output[bounds];
+            match &descriptor.ty {
+                FieldType::String => put_string(target, &value),
+                ty => {
+                    let scalar =
+                        Scalar::of(ty).expect("value fields other than strings are scalars");
+                    if !scalar.write(&value, target, *endianess) {
+                        return Err(Error::Pack(format!(
+                            "Cannot pack `{}` as {}.",
+                            value,
+                            scalar.name()
+                        )));
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Unpacks a record from the start of `data`, returning its values along with its size. Values of absent
+    /// conditional groups are `Nil`.
+    pub fn unpack(&self, data: &[u8]) -> Result<(Vec<Value>, usize), Error> {
+        if data.len() < self.size {
+            return Err(Error::Truncated(self.size));
+        }
+        if let Some(plan) = &self.plan {
+            let mut result = vec![];
+            plan.unpack(data, &mut result)?;
+            return Ok((result, self.size));
+        }
+        let mut state = UnpackState::new(data, false);
+        self.unpack_fields(&self.fields, &self.endianness, &mut state)?;
+        Ok((state.result, usize::max(state.end, self.size)))
+    }
+
+    /// Unpacks `data` recording where every field lies, padding and checksums included. Fields read before
+    /// an error are returned along with it.
+    pub fn inspect(&self, data: &[u8]) -> (Vec<FieldSpan>, Result<(), Error>) {
+        let mut state = UnpackState::new(data, true);
+        let result = self.unpack_fields(&self.fields, &self.endianness, &mut state);
+        (state.spans.unwrap_or_default(), result)
+    }
+
+    fn unpack_fields<'a>(
+        &self,
+        fields: &'a [FieldDescriptior],
+        endianness: &Endianness,
+        state: &mut UnpackState<'a, '_>,
+    ) -> Result<(), Error> {
+        let mut discriminant: Option<i64> = None;
+        for field in fields {
+            match &field.ty {
+                FieldType::Union(branches) => {
+                    let branch =
+                        UnionBranch::select(branches, discriminant).map_err(Error::Unpack)?;
+                    let endianness = branch.endianness.as_ref().unwrap_or(endianness);
+                    self.unpack_fields(&branch.fields, endianness, state)?;
+                    continue;
+                }
+                FieldType::Optional(condition) if condition.holds(&state.scope) => {
+                    self.unpack_fields(&condition.fields, endianness, state)?;
+                    continue;
+                }
+                FieldType::Optional(condition) => {
+                    for _ in 0..condition.value_count() {
+                        state.result.push(Value::Nil);
+                    }
+                    continue;
+                }
+                FieldType::Align(alignment) => {
+                    state.move_to(state.offset.next_multiple_of(*alignment))?;
+                    continue;
+                }
+                FieldType::Seek(position) => {
+                    state.move_to(*position)?;
+                    continue;
+                }
+                _ => {}
+            }
+            let start = state.offset;
+            let bounds = start..(start + field.length);
+            state.move_to(bounds.end)?;
+            let data = state.data;
+            let value = match &field.ty {
+                FieldType::String => match str::from_utf8(&data[bounds]) {
+                    Ok(string) => Value::String(string.to_string()),
+                    Err(_) => {
+                        return Err(Error::Unpack(format!(
+                            "Invalid UTF-8 in string at offset {}.",
+                            start
+                        )))
+                    }
+                },
+                FieldType::Checksum(checksum) => {
+                    let (stored, expected) = checksum.load(data, start, *endianness);
+                    if stored != expected {
+                        return Err(Error::Unpack(format!(
+                            "Checksum mismatch at offset {}: stored {}, computed {}.",
+                            start, stored, expected
+                        )));
+                    }
+                    state.record(field, start, &Value::Int(stored as _));
+                    continue;
+                }
+                FieldType::Padding => {
+                    state.record(field, start, &Value::Nil);
+                    continue;
+                }
+                ty => Scalar::of(ty)
+                    .expect("value fields other than strings are scalars")
+                    .read(&data[bounds], *endianness),
+            };
+            if field.ty.is_integer() {
+                discriminant = value.to_i64();
+                if let (Some(name), Some(value)) = (&field.name, discriminant) {
+                    state.scope.insert(name, value);
+                }
+            }
+            let value = match &field.enumeration {
+                Some(table) => table.decode(value, self.strict)?,
+                None => value,
+            };
+            state.record(field, start, &value);
+            state.result.push(value);
+        }
+        Ok(())
+    }
+}
+
+/// Where `pack` takes the value of each field from.
+pub trait ValueSource {
+    /// Value of the field called `name`, if any, or `None` once values are exhausted.
+    fn next_value(&mut self, name: Option<&str>) -> Result<Option<Value>, Error>;
+
+    /// Skips the values of an absent conditional group, `count` at most.
+    fn skip_absent(&mut self, count: usize);
+}
+
+impl<I: Iterator<Item = Value>> ValueSource for Peekable<I> {
+    fn next_value(&mut self, _: Option<&str>) -> Result<Option<Value>, Error> {
+        Ok(self.next())
+    }
+
+    fn skip_absent(&mut self, count: usize) {
+        // Absent values may either be left out or given as `Nil`.
+        for _ in 0..count {
+            if self.next_if(Value::is_nil).is_none() {
+                break;
+            }
+        }
+    }
+}
+
+/// Progress of a `pack` call across nested sequences.
+struct PackState<'a, S: ValueSource> {
+    values: S,
+    output: Vec<u8>,
+    offset: usize,
+    scope: HashMap<&'a str, i64>,
+    checksums: Vec<(Checksum, usize, Endianness)>,
+}
+
+impl<S: ValueSource> PackState<'_, S> {
+    /// Moves to `position`, growing the output with zeroes when needed.
+    fn move_to(&mut self, position: usize) {
+        self.offset = position;
+        if self.output.len() < position {
+            self.output.resize(position, 0);
+        }
+    }
+}
+
+/// Where a field was found by `inspect`, along with its decoded value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSpan {
+    /// Name of the field, if any.
+    pub name: Option<String>,
+    /// Short name of the field's type, such as `uint16` or `padding`.
+    pub kind: &'static str,
+    /// Whether the field stands for a value, as opposed to padding and checksums.
+    pub has_value: bool,
+    pub offset: usize,
+    pub length: usize,
+    /// Decoded value, the stored checksum for checksums and `Nil` for padding.
+    pub value: Value,
+}
+
+/// Progress of an `unpack` call across nested sequences.
+struct UnpackState<'a, 'b> {
+    data: &'b [u8],
+    offset: usize,
+    /// The furthest byte read, which a backward seek leaves behind `offset`.
+    end: usize,
+    result: Vec<Value>,
+    scope: HashMap<&'a str, i64>,
+    spans: Option<Vec<FieldSpan>>,
+}
+
+impl<'b> UnpackState<'_, 'b> {
+    fn new(data: &'b [u8], inspect: bool) -> Self {
+        Self {
+            data,
+            offset: 0,
+            end: 0,
+            result: vec![],
+            scope: HashMap::new(),
+            spans: inspect.then(Vec::new),
+        }
+    }
+
+    /// Records the span of `field` when inspecting, merging runs of padding.
+    fn record(&mut self, field: &FieldDescriptior, offset: usize, value: &Value) {
+        let Some(spans) = &mut self.spans else {
+            return;
+        };
+        if let (FieldType::Padding, Some(last)) = (&field.ty, spans.last_mut()) {
+            if last.kind == "padding" && last.offset + last.length == offset {
+                last.length += field.length;
+                return;
+            }
+        }
+        spans.push(FieldSpan {
+            name: field.name.clone(),
+            kind: field.ty.kind(),
+            has_value: field.ty.has_value(),
+            offset,
+            length: field.length,
+            value: value.clone(),
+        });
+    }
+
+    fn move_to(&mut self, position: usize) -> Result<(), Error> {
+        if self.data.len() < position {
+            return Err(Error::Truncated(position));
+        }
+        self.offset = position;
+        self.end = usize::max(self.end, position);
+        Ok(())
+    }
+}
@@ -0,0 +1,31 @@
+use std::fmt;
+
+/// Why a format could not be parsed, or a record packed or unpacked.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The format string is invalid.
+    Format(String),
+    /// The values do not fit the format.
+    Pack(String),
+    /// The data ends before the record does, which needs at least this many bytes.
+    Truncated(usize),
+    /// The data does not match the format.
+    Unpack(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Format(message) | Error::Pack(message) | Error::Unpack(message) => {
+                f.write_str(message)
+            }
+            Error::Truncated(size) => write!(
+                f,
+                "Data ends before the record, which needs {} bytes.",
+                size
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
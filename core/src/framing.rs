@@ -0,0 +1,311 @@
+use crate::descriptor::Endianness;
+use crate::error::Error;
+use crate::scalar::{le_bytes, put_le_bytes};
+
+/// How consecutive records are delimited on a byte stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Framing {
+    /// Records directly follow each other, their size coming from the format itself.
+    None,
+    /// Each record is preceded by its size as an unsigned 16-bit integer.
+    LengthU16,
+    /// Each record is preceded by its size as an unsigned 32-bit integer.
+    LengthU32,
+    /// Consistent Overhead Byte Stuffing, each frame ending with a zero byte.
+    Cobs,
+    /// RFC 1055 Serial Line IP, each frame being enclosed by `END` (`0xC0`) bytes.
+    Slip,
+}
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+impl Framing {
+    /// Wraps a packed record into a frame, length prefixes following the record's endianness.
+    pub fn encode(&self, payload: &[u8], endianness: Endianness) -> Result<Vec<u8>, Error> {
+        match self {
+            Framing::None => Ok(payload.to_vec()),
+            Framing::LengthU16 => {
+                let Ok(length) = u16::try_from(payload.len()) else {
+                    return Err(Error::Pack(format!(
+                        "Record of {} bytes is too long for a 16-bit length.",
+                        payload.len()
+                    )));
+                };
+                let mut output = vec![0; 2];
+                put_le_bytes(&mut output, length.to_le_bytes(), endianness);
+                output.extend_from_slice(payload);
+                Ok(output)
+            }
+            Framing::LengthU32 => {
+                let Ok(length) = u32::try_from(payload.len()) else {
+                    return Err(Error::Pack(format!(
+                        "Record of {} bytes is too long for a 32-bit length.",
+                        payload.len()
+                    )));
+                };
+                let mut output = vec![0; 4];
+                put_le_bytes(&mut output, length.to_le_bytes(), endianness);
+                output.extend_from_slice(payload);
+                Ok(output)
+            }
+            Framing::Cobs => {
+                let mut output = Vec::with_capacity(payload.len() + payload.len() / 254 + 2);
+                let mut code_index = 0;
+                output.push(0);
+                for &byte in payload {
+                    if byte != 0 {
+                        output.push(byte);
+                    }
+                    let code = (output.len() - code_index) as u8;
+                    if byte == 0 || code == 0xFF {
+                        output[code_index] = code;
+                        code_index = output.len();
+                        output.push(0);
+                    }
+                }
+                output[code_index] = (output.len() - code_index) as u8;
+                output.push(0);
+                Ok(output)
+            }
+            Framing::Slip => {
+                let mut output = Vec::with_capacity(payload.len() + 2);
+                output.push(SLIP_END);
+                for &byte in payload {
+                    match byte {
+                        SLIP_END => output.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+                        SLIP_ESC => output.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+                        _ => output.push(byte),
+                    }
+                }
+                output.push(SLIP_END);
+                Ok(output)
+            }
+        }
+    }
+
+    /// Byte ending every frame of delimited framings.
+    pub fn delimiter(&self) -> Option<u8> {
+        match self {
+            Framing::Cobs => Some(0),
+            Framing::Slip => Some(SLIP_END),
+            _ => None,
+        }
+    }
+
+    /// Looks for a complete frame at the start of `buffer`, returning its payload, or an error if malformed,
+    /// along with how many bytes it spans. Unframed records cannot be told apart without their format, so
+    /// `None` never finds any. Empty delimited frames, such as the leading `END` of SLIP, are skipped.
+    ///
+    /// Frames longer than `max_length` are errors as soon as their length is known, which for length
+    /// prefixes is before their payload is buffered: the span returned then goes beyond `buffer`.
+    pub fn decode(
+        &self,
+        buffer: &[u8],
+        endianness: Endianness,
+        max_length: usize,
+    ) -> Option<(Result<Vec<u8>, Error>, usize)> {
+        let (prefix, length) = match self {
+            Framing::None => return None,
+            Framing::LengthU16 => {
+                let length = u16::from_le_bytes(le_bytes(buffer.get(..2)?, endianness));
+                (2, length as usize)
+            }
+            Framing::LengthU32 => {
+                let length = u32::from_le_bytes(le_bytes(buffer.get(..4)?, endianness));
+                (4, length as usize)
+            }
+            Framing::Cobs | Framing::Slip => {
+                let delimiter = self.delimiter()?;
+                let skipped = buffer.iter().take_while(|byte| **byte == delimiter).count();
+                let frame = &buffer[skipped..];
+                let end = frame.iter().position(|byte| *byte == delimiter)?;
+                if end > max_length {
+                    return Some((Err(too_long(end, max_length)), skipped + end + 1));
+                }
+                let payload = if *self == Framing::Cobs {
+                    cobs_decode(&frame[..end])
+                } else {
+                    slip_decode(&frame[..end])
+                };
+                return Some((payload, skipped + end + 1));
+            }
+        };
+        if length > max_length {
+            return Some((Err(too_long(length, max_length)), prefix + length));
+        }
+        let payload = buffer.get(prefix..prefix + length)?;
+        Some((Ok(payload.to_vec()), prefix + length))
+    }
+}
+
+fn cobs_decode(frame: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::with_capacity(frame.len());
+    let mut index = 0;
+    while index < frame.len() {
+        let code = frame[index] as usize;
+        let Some(block) = frame.get(index + 1..index + code) else {
+            return Err(Error::Unpack("Malformed COBS frame.".to_string()));
+        };
+        output.extend_from_slice(block);
+        index += code;
+        if code < 0xFF && index < frame.len() {
+            output.push(0);
+        }
+    }
+    Ok(output)
+}
+
+fn slip_decode(frame: &[u8]) -> Result<Vec<u8>, Error> {
+    let mut output = Vec::with_capacity(frame.len());
+    let mut bytes = frame.iter();
+    while let Some(&byte) = bytes.next() {
+        if byte != SLIP_ESC {
+            output.push(byte);
+            continue;
+        }
+        match bytes.next() {
+            Some(&SLIP_ESC_END) => output.push(SLIP_END),
+            Some(&SLIP_ESC_ESC) => output.push(SLIP_ESC),
+            _ => return Err(Error::Unpack("Malformed SLIP escape sequence.".to_string())),
+        }
+    }
+    Ok(output)
+}
+
+fn too_long(length: usize, max_length: usize) -> Error {
+    Error::Unpack(format!(
+        "Frame of {} bytes is longer than the maximum of {}, dropping it.",
+        length, max_length
+    ))
+}
+
+/// What is left to drop of a frame too long to be buffered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Discard {
+    Nothing,
+    /// The rest of a length prefixed frame.
+    Bytes(usize),
+    /// Everything up to the end of a delimited frame.
+    ToDelimiter,
+}
+
+/// Splits a byte stream delivered in arbitrary chunks, as TCP does, into frames. Bytes are buffered by `feed`
+/// until whole frames are available, frames longer than the maximum length being dropped as they arrive
+/// rather than buffered, so that a bogus length cannot make the buffer grow without bounds.
+#[derive(Debug, Clone)]
+pub struct FrameDecoder {
+    framing: Framing,
+    endianness: Endianness,
+    max_length: Option<usize>,
+    buffer: Vec<u8>,
+    /// Bytes at the start of `buffer` already taken out.
+    start: usize,
+    discarding: Discard,
+}
+
+impl FrameDecoder {
+    /// Longest frame accepted unless changed, 1 MiB.
+    pub const DEFAULT_MAX_LENGTH: usize = 1 << 20;
+
+    pub fn new(framing: Framing, endianness: Endianness) -> Self {
+        Self {
+            framing,
+            endianness,
+            max_length: Some(Self::DEFAULT_MAX_LENGTH),
+            buffer: vec![],
+            start: 0,
+            discarding: Discard::Nothing,
+        }
+    }
+
+    pub fn framing(&self) -> Framing {
+        self.framing
+    }
+
+    /// Sets the length of the longest frame accepted, without limit if `None`.
+    pub fn set_max_length(&mut self, max_length: Option<usize>) {
+        self.max_length = max_length;
+    }
+
+    pub fn max_length(&self) -> Option<usize> {
+        self.max_length
+    }
+
+    /// Buffers `data`, dropping first whatever is left of a frame too long to be buffered.
+    pub fn feed(&mut self, data: &[u8]) {
+        self.buffer.drain(..self.start);
+        self.start = 0;
+        let data = self.discard(data);
+        self.buffer.extend_from_slice(data);
+    }
+
+    /// Takes the next complete frame out of the buffer, or `None` while there is none. Malformed and too
+    /// long frames are dropped and returned as errors. Unframed records are left to be taken out with
+    /// `consume`, since only their format tells their size.
+    pub fn next_frame(&mut self) -> Option<Result<Vec<u8>, Error>> {
+        let buffer = self.buffered();
+        let max_length = self.max_length.unwrap_or(usize::MAX);
+        let Some((payload, size)) = self.framing.decode(buffer, self.endianness, max_length) else {
+            // Delimited frames only get a length once complete, so an incomplete one may already be too long.
+            if self.framing.delimiter().is_some() && buffer.len() > max_length {
+                let error = too_long(buffer.len(), max_length);
+                self.discarding = Discard::ToDelimiter;
+                self.start = self.buffer.len();
+                return Some(Err(error));
+            }
+            return None;
+        };
+        if size > buffer.len() {
+            self.discarding = Discard::Bytes(size - buffer.len());
+            self.start = self.buffer.len();
+        } else {
+            self.start += size;
+        }
+        Some(payload)
+    }
+
+    /// Bytes buffered but not taken out yet.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buffer[self.start..]
+    }
+
+    /// Takes `count` bytes out of the buffer, such as an unframed record once unpacked.
+    pub fn consume(&mut self, count: usize) {
+        self.start = usize::min(self.start + count, self.buffer.len());
+    }
+
+    /// Drops every buffered byte, along with what is left of a too long frame.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.start = 0;
+        self.discarding = Discard::Nothing;
+    }
+
+    /// Drops the start of `data` still belonging to a frame too long to be buffered, returning the rest.
+    fn discard<'d>(&mut self, data: &'d [u8]) -> &'d [u8] {
+        match self.discarding {
+            Discard::Nothing => data,
+            Discard::Bytes(count) if count > data.len() => {
+                self.discarding = Discard::Bytes(count - data.len());
+                &[]
+            }
+            Discard::Bytes(count) => {
+                self.discarding = Discard::Nothing;
+                &data[count..]
+            }
+            Discard::ToDelimiter => {
+                let delimiter = self.framing.delimiter();
+                match data.iter().position(|byte| Some(*byte) == delimiter) {
+                    Some(end) => {
+                        self.discarding = Discard::Nothing;
+                        &data[end..]
+                    }
+                    None => &[],
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,20 @@
+//! Engine independent core of `godot-pack`: parses format strings and packs values into binary records,
+//! or unpacks them back. The GDExtension wraps it, converting between `Value` and Godot variants.
+mod cache;
+mod checksum;
+mod columns;
+mod descriptor;
+mod error;
+mod framing;
+mod parser;
+mod plan;
+mod pool;
+mod scalar;
+mod value;
+
+pub use cache::{CacheStats, FormatCache};
+pub use columns::{Column, RECORDS_PER_THREAD};
+pub use descriptor::{Endianness, FieldSpan, PackingDescriptor, ValueSource};
+pub use error::Error;
+pub use framing::{FrameDecoder, Framing};
+pub use value::{Value, ValueKind};
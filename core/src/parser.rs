@@ -0,0 +1,309 @@
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::checksum::Checksum;
+use crate::descriptor::{Condition, Endianness, FieldDescriptior, FieldType, UnionBranch};
+use crate::error::Error;
+
+/// Deepest nesting of unions and conditions, which are parsed, packed and unpacked recursively.
+const MAX_DEPTH: usize = 32;
+
+/// Largest position and alignment, which keeps every offset and packed record within bounds.
+const MAX_POSITION: i64 = 1 << 24;
+
+/// Recursive descent over a format string, unions and conditions being parsed as nested sequences.
+pub(crate) struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    integers: Vec<String>,
+    depth: usize,
+}
+
+/// Whether `c` is a code a count may precede.
+fn is_code(c: char) -> bool {
+    "sx?cbBhHiIlLqQfdWCA".contains(c)
+}
+
+fn invalid<T>(message: impl Into<String>) -> Result<T, Error> {
+    Err(Error::Format(message.into()))
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(format: &'a str) -> Self {
+        Self {
+            chars: format.chars().peekable(),
+            integers: vec![],
+            depth: 0,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    /// Parses a field name, whose dots separate the steps of a property path such as `position.x`.
+    fn identifier(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(c) = self
+            .chars
+            .next_if(|c| c.is_alphanumeric() || *c == '_' || *c == '.')
+        {
+            name.push(c);
+        }
+        name
+    }
+
+    /// Parses a decimal or `0x` prefixed hexadecimal, optionally negative, number.
+    fn number(&mut self) -> Result<i64, Error> {
+        let negative = self.chars.next_if_eq(&'-').is_some();
+        let mut radix = 10;
+        if self.chars.next_if_eq(&'0').is_some() {
+            if self.chars.next_if(|c| *c == 'x' || *c == 'X').is_some() {
+                radix = 16;
+            } else if !self.chars.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Ok(0);
+            }
+        }
+        let mut digits = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_digit(radix)) {
+            digits.push(c);
+        }
+        match i64::from_str_radix(&digits, radix) {
+            Ok(value) if negative => Ok(-value),
+            Ok(value) => Ok(value),
+            Err(_) => invalid("Invalid number in pattern."),
+        }
+    }
+
+    /// Parses fields until the end of input or until one of `terminators`, which is left unconsumed.
+    pub(crate) fn sequence(
+        &mut self,
+        terminators: &[char],
+    ) -> Result<(Vec<FieldDescriptior>, Option<Endianness>), Error> {
+        if self.depth > MAX_DEPTH {
+            return invalid("Unions and conditions are nested too deeply.");
+        }
+        self.depth += 1;
+        let result = self.fields(terminators);
+        self.depth -= 1;
+        result
+    }
+
+    fn fields(
+        &mut self,
+        terminators: &[char],
+    ) -> Result<(Vec<FieldDescriptior>, Option<Endianness>), Error> {
+        let mut order: Option<Endianness> = None;
+        let mut fields: Vec<FieldDescriptior> = vec![];
+
+        // Digits preceding a code: the length of strings and padding, or how many times to repeat others.
+        let mut count: Option<usize> = None;
+
+        while let Some(&c) = self.chars.peek() {
+            if terminators.contains(&c) {
+                break;
+            }
+            self.chars.next();
+            if let Some(digit) = c.to_digit(10) {
+                let value = count.unwrap_or(0).saturating_mul(10);
+                count = Some(value.saturating_add(digit as usize));
+                continue;
+            } else if count.is_some() && !is_code(c) {
+                return invalid("A count must directly precede its code.");
+            } else if c.is_whitespace() {
+                continue;
+            }
+            let (ty, length) = match c {
+                '@' | '=' => {
+                    order = Some(Endianness::NATIVE);
+                    continue;
+                }
+                '<' => {
+                    order = Some(Endianness::LittleEndian);
+                    continue;
+                }
+                '>' => {
+                    order = Some(Endianness::BigEndian);
+                    continue;
+                }
+                '!' => {
+                    order = Some(Endianness::NETWORK);
+                    continue;
+                }
+                's' => (
+                    FieldType::String,
+                    count.take().unwrap_or(1).min(u16::MAX as _),
+                ),
+                'x' => (
+                    FieldType::Padding,
+                    count.take().unwrap_or(1).min(u16::MAX as _),
+                ),
+                '%' => match self.number()? {
+                    alignment @ 1..=MAX_POSITION => (FieldType::Align(alignment as _), 0),
+                    alignment @ 1.. => {
+                        return invalid(format!("Alignment {} is too large.", alignment))
+                    }
+                    _ => return invalid("Alignment must be positive."),
+                },
+                '#' => match self.number()? {
+                    position @ 0..=MAX_POSITION => (FieldType::Seek(position as _), 0),
+                    position @ 0.. => {
+                        return invalid(format!("Position {} is too large.", position))
+                    }
+                    _ => return invalid("Position cannot be negative."),
+                },
+                '?' if self.is_condition() => (FieldType::Optional(self.condition()?), 0),
+                '?' => (FieldType::Bool, core::mem::size_of::<bool>()),
+                'c' => (FieldType::Character, core::mem::size_of::<u8>()),
+                'b' => (FieldType::Char, core::mem::size_of::<i8>()),
+                'B' => (FieldType::UnsignedChar, core::mem::size_of::<u8>()),
+                'h' => (FieldType::Short, core::mem::size_of::<i16>()),
+                'H' => (FieldType::UnsignedShort, core::mem::size_of::<u16>()),
+                'i' => (FieldType::Int, core::mem::size_of::<std::ffi::c_int>()),
+                'I' => (
+                    FieldType::UnsignedInt,
+                    core::mem::size_of::<std::ffi::c_uint>(),
+                ),
+                'l' => (FieldType::Long, core::mem::size_of::<i32>()),
+                'L' => (FieldType::UnsignedLong, core::mem::size_of::<u32>()),
+                'q' => (FieldType::LongLong, core::mem::size_of::<i64>()),
+                'Q' => (FieldType::UnsignedLongLong, core::mem::size_of::<u64>()),
+                'f' => (FieldType::Float, core::mem::size_of::<f32>()),
+                'd' => (FieldType::Double, core::mem::size_of::<f64>()),
+                'W' => (FieldType::Checksum(Checksum::Crc16), Checksum::Crc16.size()),
+                'C' => (FieldType::Checksum(Checksum::Crc32), Checksum::Crc32.size()),
+                'A' => (
+                    FieldType::Checksum(Checksum::Adler32),
+                    Checksum::Adler32.size(),
+                ),
+                '{' => {
+                    if !fields.last().is_some_and(|field| field.ty.is_integer()) {
+                        return invalid("A union must directly follow an integer discriminant.");
+                    }
+                    (FieldType::Union(self.union()?), 0)
+                }
+                _ => return invalid(format!("Invalid pattern character `{}`.", c)),
+            };
+            let repeat = match count.take() {
+                Some(repeat) if ty.has_value() || matches!(ty, FieldType::Checksum(_)) => repeat,
+                _ => 1,
+            };
+            if repeat > u16::MAX as usize {
+                return invalid(format!("Repeat count of `{}` is too large.", c));
+            }
+
+            let mut field = FieldDescriptior {
+                ty,
+                length,
+                name: None,
+                enumeration: None,
+            };
+            if self.chars.next_if_eq(&':').is_some() {
+                let name = self.identifier();
+                if name.split('.').any(str::is_empty) || !field.ty.has_value() || repeat != 1 {
+                    return invalid(format!("Invalid field name after `{}`.", c));
+                }
+                if fields
+                    .iter()
+                    .any(|field| field.name.as_ref() == Some(&name))
+                {
+                    return invalid(format!("Duplicated field name `{}`.", name));
+                }
+                if field.ty.is_integer() {
+                    self.integers.push(name.clone());
+                }
+                field.name = Some(name);
+            }
+            fields.extend(std::iter::repeat_n(field, repeat));
+        }
+        if count.is_some() {
+            return invalid("A count must directly precede its code.");
+        }
+
+        Ok((fields, order))
+    }
+
+    /// Parses the branches of a union, after its opening `{` and up to the closing `}`.
+    fn union(&mut self) -> Result<Vec<UnionBranch>, Error> {
+        let mut branches: Vec<UnionBranch> = vec![];
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&'}').is_some() {
+                break;
+            }
+            let key = if self.chars.next_if_eq(&'_').is_some() {
+                None
+            } else {
+                Some(self.number()?)
+            };
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&':').is_none() {
+                return invalid("Expected `:` after union key.");
+            }
+            if branches.iter().any(|branch| branch.key == key) {
+                return invalid("Duplicated union key.");
+            }
+            let (fields, endianness) = self.sequence(&[',', '}'])?;
+            branches.push(UnionBranch {
+                key,
+                endianness,
+                fields,
+            });
+            match self.chars.next() {
+                Some(',') => continue,
+                Some('}') => break,
+                _ => return invalid("Unterminated union."),
+            }
+        }
+        if branches.is_empty() {
+            return invalid("Empty union.");
+        }
+        Ok(branches)
+    }
+
+    /// Tells a condition (`?name&mask(` or `?name(`) apart from a boolean, right after the `?`.
+    fn is_condition(&self) -> bool {
+        let mut probe = Parser {
+            chars: self.chars.clone(),
+            integers: vec![],
+            depth: 0,
+        };
+        !probe.identifier().is_empty() && matches!(probe.chars.peek(), Some('&' | '('))
+    }
+
+    /// Parses a condition after its `?`, up to the closing `)` of its group.
+    fn condition(&mut self) -> Result<Condition, Error> {
+        let field = self.identifier();
+        if !self.integers.contains(&field) {
+            return invalid(format!(
+                "Condition on `{}` which is not a preceding integer field.",
+                field
+            ));
+        }
+        let mask = match self.chars.next_if_eq(&'&') {
+            Some(_) => Some(self.number()?),
+            None => None,
+        };
+        if self.chars.next_if_eq(&'(').is_none() {
+            return invalid("Expected `(` after condition.");
+        }
+        let (fields, endianness) = self.sequence(&[')'])?;
+        if endianness.is_some() {
+            return invalid("Endianness cannot be changed within a condition.");
+        }
+        // Absent groups stand for a fixed number of `null`s, which a union's branches would not agree on.
+        if fields
+            .iter()
+            .any(|field| matches!(field.ty, FieldType::Union(_)))
+        {
+            return invalid("Unions cannot be placed within a condition.");
+        }
+        if self.chars.next_if_eq(&')').is_none() {
+            return invalid("Unterminated condition.");
+        }
+        Ok(Condition {
+            field,
+            mask,
+            fields,
+        })
+    }
+}
@@ -0,0 +1,184 @@
+use crate::checksum::Checksum;
+use crate::descriptor::{Endianness, FieldDescriptior, FieldType, ValueSource};
+use crate::error::Error;
+use crate::scalar::{put_string, swap_bytes, Scalar};
+use crate::value::Value;
+
+/// One operation of a plan, at a precomputed offset of the record.
+#[derive(Debug, Clone)]
+enum Step {
+    /// Values of the same type back to back, one per name.
+    Scalars {
+        offset: usize,
+        scalar: Scalar,
+        names: Vec<Option<String>>,
+    },
+    String {
+        offset: usize,
+        length: usize,
+        name: Option<String>,
+    },
+    Checksum {
+        offset: usize,
+        checksum: Checksum,
+    },
+}
+
+/// A fixed layout compiled into straight line steps, so that packing and unpacking no longer walk the
+/// fields: offsets are resolved, padding disappears and runs of same typed fields are handled as one loop.
+#[derive(Debug, Clone)]
+pub(crate) struct Plan {
+    steps: Vec<Step>,
+    endianness: Endianness,
+}
+
+impl Plan {
+    /// Compiles the fields of a fixed layout, as found by `fixed_layout`. Enum fields are left to the
+    /// descriptor.
+    pub(crate) fn compile(
+        layout: &[(usize, &FieldDescriptior)],
+        endianness: Endianness,
+    ) -> Option<Plan> {
+        let mut steps: Vec<Step> = vec![];
+        for (offset, field) in layout {
+            if field.enumeration.is_some() {
+                return None;
+            }
+            let offset = *offset;
+            let step = match &field.ty {
+                FieldType::Padding => continue,
+                FieldType::Checksum(checksum) => Step::Checksum {
+                    offset,
+                    checksum: *checksum,
+                },
+                FieldType::String => Step::String {
+                    offset,
+                    length: field.length,
+                    name: field.name.clone(),
+                },
+                ty => {
+                    let scalar = Scalar::of(ty)?;
+                    if let Some(Step::Scalars {
+                        offset: start,
+                        scalar: previous,
+                        names,
+                    }) = steps.last_mut()
+                    {
+                        if *previous == scalar && *start + scalar.size() * names.len() == offset {
+                            names.push(field.name.clone());
+                            continue;
+                        }
+                    }
+                    Step::Scalars {
+                        offset,
+                        scalar,
+                        names: vec![field.name.clone()],
+                    }
+                }
+            };
+            steps.push(step);
+        }
+        Some(Plan { steps, endianness })
+    }
+
+    /// Unpacks the values of a record from `data`, which holds at least a whole record.
+    pub(crate) fn unpack(&self, data: &[u8], result: &mut Vec<Value>) -> Result<(), Error> {
+        for step in &self.steps {
+            match step {
+                Step::Scalars {
+                    offset,
+                    scalar,
+                    names,
+                } => {
+                    let run = &data[*offset..*offset + scalar.size() * names.len()];
+                    scalar.read_run(run, self.endianness, result);
+                }
+                Step::String { offset, length, .. } => {
+                    match str::from_utf8(&data[*offset..*offset + length]) {
+                        Ok(string) => result.push(Value::String(string.to_string())),
+                        Err(_) => {
+                            return Err(Error::Unpack(format!(
+                                "Invalid UTF-8 in string at offset {}.",
+                                offset
+                            )))
+                        }
+                    }
+                }
+                Step::Checksum { offset, checksum } => {
+                    let (stored, expected) = checksum.load(data, *offset, self.endianness);
+                    if stored != expected {
+                        return Err(Error::Unpack(format!(
+                            "Checksum mismatch at offset {}: stored {}, computed {}.",
+                            offset, stored, expected
+                        )));
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Packs the values of `values` into `output`, a zeroed record. Fields left without a value stay zeroed.
+    pub(crate) fn pack(
+        &self,
+        values: &mut impl ValueSource,
+        output: &mut [u8],
+    ) -> Result<(), Error> {
+        for step in &self.steps {
+            match step {
+                Step::Scalars {
+                    offset,
+                    scalar,
+                    names,
+                } => {
+                    // Runs are written in native order, then swapped as a whole if needed.
+                    let size = scalar.size();
+                    let run = &mut output[*offset..*offset + size * names.len()];
+                    let mut written = 0;
+                    let mut exhausted = false;
+                    for (target, name) in run.chunks_exact_mut(size).zip(names) {
+                        let Some(value) = values.next_value(name.as_deref())? else {
+                            exhausted = true;
+                            break;
+                        };
+                        if !scalar.write(&value, target, Endianness::NATIVE) {
+                            return Err(Error::Pack(format!(
+                                "Cannot pack `{}` as {}.",
+                                value,
+                                scalar.name()
+                            )));
+                        }
+                        written += size;
+                    }
+                    if self.endianness != Endianness::NATIVE {
+                        swap_bytes(&mut run[..written], size);
+                    }
+                    if exhausted {
+                        return Ok(());
+                    }
+                }
+                Step::String {
+                    offset,
+                    length,
+                    name,
+                } => {
+                    let Some(value) = values.next_value(name.as_deref())? else {
+                        return Ok(());
+                    };
+                    put_string(&mut output[*offset..*offset + length], &value);
+                }
+                Step::Checksum { .. } => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the checksums of a packed record.
+    pub(crate) fn write_checksums(&self, output: &mut [u8]) {
+        for step in &self.steps {
+            if let Step::Checksum { offset, checksum } = step {
+                checksum.store(output, *offset, self.endianness);
+            }
+        }
+    }
+}
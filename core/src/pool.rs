@@ -0,0 +1,106 @@
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Worker threads shared by every parallel batch, started on first use and kept for the whole process, so
+/// that loading a replay batch after batch does not start new threads each time.
+struct Pool {
+    sender: Sender<Job>,
+    receiver: Arc<Mutex<Receiver<Job>>>,
+    /// Number of workers started so far.
+    workers: Mutex<usize>,
+}
+
+impl Pool {
+    fn get() -> &'static Pool {
+        static POOL: OnceLock<Pool> = OnceLock::new();
+        POOL.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel();
+            Pool {
+                sender,
+                receiver: Arc::new(Mutex::new(receiver)),
+                workers: Mutex::new(0),
+            }
+        })
+    }
+
+    /// Starts workers until there are at least `count` of them, returning how many there are.
+    fn grow(&self, count: usize) -> usize {
+        let mut workers = self
+            .workers
+            .lock()
+            .unwrap_or_else(|error| error.into_inner());
+        while *workers < count {
+            let receiver = Arc::clone(&self.receiver);
+            let worker = thread::Builder::new()
+                .name("godot-pack".to_string())
+                .spawn(move || work(&receiver));
+            if worker.is_err() {
+                break;
+            }
+            *workers += 1;
+        }
+        *workers
+    }
+}
+
+/// Runs jobs until the pool is gone.
+fn work(receiver: &Mutex<Receiver<Job>>) {
+    loop {
+        // The lock is released before running the job, letting other workers take the next one.
+        let job = receiver
+            .lock()
+            .unwrap_or_else(|error| error.into_inner())
+            .recv();
+        match job {
+            Ok(job) => job(),
+            Err(_) => break,
+        }
+    }
+}
+
+/// Runs every task on the shared pool, with at least as many workers as tasks, returning their results in
+/// order once all of them are done. A panicking task is forwarded to the caller after the others finished.
+pub(crate) fn run<'a, T: Send + 'a>(tasks: Vec<Box<dyn FnOnce() -> T + Send + 'a>>) -> Vec<T> {
+    let pool = Pool::get();
+    let workers = pool.grow(tasks.len());
+    let (sender, receiver) = mpsc::channel::<(usize, Result<T, Box<dyn Any + Send>>)>();
+    let count = tasks.len();
+    for (index, task) in tasks.into_iter().enumerate() {
+        let sender = sender.clone();
+        let job: Box<dyn FnOnce() + Send + 'a> = Box::new(move || {
+            let result = panic::catch_unwind(AssertUnwindSafe(task));
+            let _ = sender.send((index, result));
+        });
+        // SAFETY: the job only borrows data living for `'a`, and it is either run or dropped, along with its
+        // borrows and its result sender, before the loop below stops receiving and this function returns.
+        let job = unsafe { std::mem::transmute::<Box<dyn FnOnce() + Send + 'a>, Job>(job) };
+        // Without any worker, say if threads cannot be started, the caller runs the job itself.
+        if workers == 0 {
+            job();
+        } else if let Err(error) = pool.sender.send(job) {
+            (error.0)();
+        }
+    }
+    drop(sender);
+    let mut results: Vec<Option<T>> = (0..count).map(|_| None).collect();
+    let mut panic = None;
+    // Only stops once every job dropped its sender.
+    for (index, result) in receiver {
+        match result {
+            Ok(value) => results[index] = Some(value),
+            Err(payload) => panic = Some(payload),
+        }
+    }
+    if let Some(payload) = panic {
+        panic::resume_unwind(payload);
+    }
+    results
+        .into_iter()
+        .map(|result| result.expect("every pooled task is run"))
+        .collect()
+}
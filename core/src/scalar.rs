@@ -0,0 +1,247 @@
+use crate::descriptor::{Endianness, FieldType};
+use crate::value::Value;
+
+/// Copies the bytes of a field, in little-endian order whatever the record's byte order.
+pub(crate) fn le_bytes<const N: usize>(bytes: &[u8], endianness: Endianness) -> [u8; N] {
+    let mut array = [0; N];
+    array.copy_from_slice(bytes);
+    if endianness == Endianness::BigEndian {
+        array.reverse();
+    }
+    array
+}
+
+/// Writes little-endian `bytes` into a field, in the record's byte order.
+pub(crate) fn put_le_bytes<const N: usize>(
+    target: &mut [u8],
+    mut bytes: [u8; N],
+    endianness: Endianness,
+) {
+    if endianness == Endianness::BigEndian {
+        bytes.reverse();
+    }
+    target.copy_from_slice(&bytes);
+}
+
+/// Writes the text of `value` into a string field, truncated or zero filled to its length.
+pub(crate) fn put_string(target: &mut [u8], value: &Value) {
+    let string = value.to_string();
+    let length = usize::min(string.len(), target.len());
+    target[..length].copy_from_slice(&string.as_bytes()[..length]);
+}
+
+/// Fixed size values, every field other than strings and layout constructs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Scalar {
+    Bool,
+    Character,
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    I64,
+    U64,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    pub(crate) fn of(ty: &FieldType) -> Option<Scalar> {
+        let scalar = match ty {
+            FieldType::Bool => Scalar::Bool,
+            FieldType::Character => Scalar::Character,
+            FieldType::Char => Scalar::I8,
+            FieldType::UnsignedChar => Scalar::U8,
+            FieldType::Short => Scalar::I16,
+            FieldType::UnsignedShort => Scalar::U16,
+            FieldType::Int | FieldType::Long => Scalar::I32,
+            FieldType::UnsignedInt | FieldType::UnsignedLong => Scalar::U32,
+            FieldType::LongLong => Scalar::I64,
+            FieldType::UnsignedLongLong => Scalar::U64,
+            FieldType::Float => Scalar::F32,
+            FieldType::Double => Scalar::F64,
+            _ => return None,
+        };
+        Some(scalar)
+    }
+
+    pub(crate) fn size(self) -> usize {
+        match self {
+            Scalar::Bool | Scalar::Character | Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::I64 | Scalar::U64 | Scalar::F64 => 8,
+        }
+    }
+
+    /// Name of the type in error messages.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Scalar::Bool => "bool",
+            Scalar::Character => "char",
+            Scalar::I8 => "i8",
+            Scalar::U8 => "u8",
+            Scalar::I16 => "i16",
+            Scalar::U16 => "u16",
+            Scalar::I32 => "i32",
+            Scalar::U32 => "u32",
+            Scalar::I64 => "i64",
+            Scalar::U64 => "u64",
+            Scalar::F32 => "f32",
+            Scalar::F64 => "f64",
+        }
+    }
+
+    /// Reads the value of a field from its `bytes`.
+    pub(crate) fn read(self, bytes: &[u8], endianness: Endianness) -> Value {
+        macro_rules! read {
+            ($variant:ident, $T:ty) => {
+                Value::$variant(<$T>::from_le_bytes(le_bytes(bytes, endianness)) as _)
+            };
+        }
+        match self {
+            Scalar::Bool => Value::Bool(bytes[0] != 0),
+            // Bytes map to the first 256 code points, as in Latin-1, so that any of them round-trips.
+            Scalar::Character => Value::String(char::from(bytes[0]).to_string()),
+            Scalar::I8 => read!(Int, i8),
+            Scalar::U8 => read!(Int, u8),
+            Scalar::I16 => read!(Int, i16),
+            Scalar::U16 => read!(Int, u16),
+            Scalar::I32 => read!(Int, i32),
+            Scalar::U32 => read!(Int, u32),
+            Scalar::I64 => read!(Int, i64),
+            Scalar::U64 => read!(Int, u64),
+            Scalar::F32 => read!(Float, f32),
+            Scalar::F64 => read!(Float, f64),
+        }
+    }
+
+    /// Reads the values of `bytes`, a run of back to back fields of this type, into `result`. Runs in a
+    /// foreign byte order are swapped as a whole first, with SIMD where available.
+    pub(crate) fn read_run(self, bytes: &[u8], endianness: Endianness, result: &mut Vec<Value>) {
+        let swapped;
+        let (bytes, endianness) = if endianness != Endianness::NATIVE && bytes.len() >= SIMD_WIDTH {
+            swapped = {
+                let mut swapped = bytes.to_vec();
+                swap_bytes(&mut swapped, self.size());
+                swapped
+            };
+            (swapped.as_slice(), Endianness::NATIVE)
+        } else {
+            (bytes, endianness)
+        };
+        // The type is matched once per run rather than once per value.
+        macro_rules! read {
+            ($variant:ident, $T:ty) => {
+                result.extend(bytes.chunks_exact(size_of::<$T>()).map(|bytes| {
+                    Value::$variant(<$T>::from_le_bytes(le_bytes(bytes, endianness)) as _)
+                }))
+            };
+        }
+        match self {
+            Scalar::Bool => result.extend(bytes.iter().map(|byte| Value::Bool(*byte != 0))),
+            Scalar::Character => result.extend(
+                bytes
+                    .iter()
+                    .map(|byte| Value::String(char::from(*byte).to_string())),
+            ),
+            Scalar::I8 => read!(Int, i8),
+            Scalar::U8 => read!(Int, u8),
+            Scalar::I16 => read!(Int, i16),
+            Scalar::U16 => read!(Int, u16),
+            Scalar::I32 => read!(Int, i32),
+            Scalar::U32 => read!(Int, u32),
+            Scalar::I64 => read!(Int, i64),
+            Scalar::U64 => read!(Int, u64),
+            Scalar::F32 => read!(Float, f32),
+            Scalar::F64 => read!(Float, f64),
+        }
+    }
+
+    /// Writes `value` into the `bytes` of a field, returning `false` if it does not fit the type.
+    pub(crate) fn write(self, value: &Value, bytes: &mut [u8], endianness: Endianness) -> bool {
+        macro_rules! write {
+            ($converted:expr) => {
+                match $converted {
+                    Some(converted) => {
+                        put_le_bytes(bytes, converted.to_le_bytes(), endianness);
+                        true
+                    }
+                    None => false,
+                }
+            };
+        }
+        macro_rules! write_integer {
+            ($T:ty) => {
+                write!(value.to_i64().and_then(|value| <$T>::try_from(value).ok()))
+            };
+        }
+        match self {
+            Scalar::Bool => write!(value.to_bool().map(u8::from)),
+            Scalar::Character => write!(value.to_char_byte()),
+            Scalar::I8 => write_integer!(i8),
+            Scalar::U8 => write_integer!(u8),
+            Scalar::I16 => write_integer!(i16),
+            Scalar::U16 => write_integer!(u16),
+            Scalar::I32 => write_integer!(i32),
+            Scalar::U32 => write_integer!(u32),
+            Scalar::I64 => write_integer!(i64),
+            // Godot integers are signed, so the upper half of the range wraps around.
+            Scalar::U64 => write!(value.to_i64().map(|value| value as u64)),
+            Scalar::F32 => write!(value.to_f64().map(|value| value as f32)),
+            Scalar::F64 => write!(value.to_f64()),
+        }
+    }
+}
+
+/// Bytes swapped at once by `swap_bytes`, a multiple of every field size.
+const SIMD_WIDTH: usize = 16;
+
+/// Reverses the byte order of every `size` bytes long element of `bytes`.
+pub(crate) fn swap_bytes(bytes: &mut [u8], size: usize) {
+    if size < 2 {
+        return;
+    }
+    let swapped = swap_blocks(bytes, size);
+    for element in bytes[swapped..].chunks_exact_mut(size) {
+        element.reverse();
+    }
+}
+
+/// Swaps whole `SIMD_WIDTH` bytes blocks of `bytes` with a single shuffle each, returning how many bytes
+/// were swapped.
+#[cfg(target_arch = "x86_64")]
+fn swap_blocks(bytes: &mut [u8], size: usize) -> usize {
+    if !std::arch::is_x86_feature_detected!("ssse3") {
+        return 0;
+    }
+    // SAFETY: SSSE3 is available.
+    unsafe { swap_blocks_ssse3(bytes, size) }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "ssse3")]
+unsafe fn swap_blocks_ssse3(bytes: &mut [u8], size: usize) -> usize {
+    use std::arch::x86_64::{__m128i, _mm_loadu_si128, _mm_shuffle_epi8, _mm_storeu_si128};
+
+    // Byte `index` of a block takes the mirrored byte of the same element.
+    let mask: [u8; SIMD_WIDTH] =
+        std::array::from_fn(|index| (index - index % size + size - 1 - index % size) as u8);
+    let length = bytes.len() - bytes.len() % SIMD_WIDTH;
+    // SAFETY: every load and store stays within `bytes[..length]`, unaligned accesses being allowed.
+    unsafe {
+        let mask = _mm_loadu_si128(mask.as_ptr().cast());
+        for block in bytes[..length].chunks_exact_mut(SIMD_WIDTH) {
+            let pointer = block.as_mut_ptr().cast::<__m128i>();
+            _mm_storeu_si128(pointer, _mm_shuffle_epi8(_mm_loadu_si128(pointer), mask));
+        }
+    }
+    length
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn swap_blocks(_: &mut [u8], _: usize) -> usize {
+    0
+}
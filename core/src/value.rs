@@ -0,0 +1,111 @@
+use std::fmt;
+
+/// A packed or unpacked value, mirroring the variant types a format deals with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Nil,
+    Bool(bool),
+    /// Every integer field, 64-bit unsigned values above `i64::MAX` wrapping around to negative numbers.
+    Int(i64),
+    Float(f64),
+    String(String),
+    /// Symbolic name of an enum value, a `StringName` in Godot.
+    Name(String),
+}
+
+/// Type of the values of a field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueKind {
+    Bool,
+    Int,
+    Float,
+    String,
+}
+
+impl Value {
+    pub fn is_nil(&self) -> bool {
+        matches!(self, Value::Nil)
+    }
+
+    /// Converts booleans, integers and floats (truncated) into an integer.
+    pub fn to_i64(&self) -> Option<i64> {
+        match self {
+            Value::Bool(value) => Some(*value as i64),
+            Value::Int(value) => Some(*value),
+            Value::Float(value) if value.is_finite() => {
+                let truncated = value.trunc();
+                (truncated >= i64::MIN as f64 && truncated < i64::MAX as f64)
+                    .then_some(truncated as i64)
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts booleans, integers and floats into a float.
+    pub fn to_f64(&self) -> Option<f64> {
+        match self {
+            Value::Bool(value) => Some(*value as i64 as f64),
+            Value::Int(value) => Some(*value as f64),
+            Value::Float(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    /// Converts the first character of the value's text into the byte of a `c` field, the first 256 code
+    /// points mapping to the byte of the same value as in Latin-1. Empty text gives a zero byte.
+    pub fn to_char_byte(&self) -> Option<u8> {
+        u8::try_from(self.to_string().chars().next().unwrap_or('\0')).ok()
+    }
+
+    /// Converts booleans, integers and floats into a boolean, true unless zero.
+    pub fn to_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(value) => Some(*value),
+            Value::Int(value) => Some(*value != 0),
+            Value::Float(value) => Some(*value != 0.0),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Nil => Ok(()),
+            Value::Bool(value) => write!(f, "{}", value),
+            Value::Int(value) => write!(f, "{}", value),
+            Value::Float(value) => write!(f, "{:?}", value),
+            Value::String(value) | Value::Name(value) => f.write_str(value),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::Int(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::Float(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.to_string())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
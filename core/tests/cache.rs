@@ -0,0 +1,85 @@
+use godot_pack_core::{CacheStats, FormatCache};
+
+/// Looks `format` up, creating its length as the value when missing.
+fn lookup(cache: &mut FormatCache<usize>, format: &str) -> Option<usize> {
+    cache
+        .get_or_insert_with(format, |format| Some(format.len()))
+        .copied()
+}
+
+/// Whether `format` is cached, looking it up without creating it.
+fn contains(cache: &mut FormatCache<usize>, format: &str) -> bool {
+    cache.get_or_insert_with(format, |_| None).is_some()
+}
+
+fn stats(hits: u64, misses: u64, evictions: u64, size: usize) -> CacheStats {
+    CacheStats {
+        hits,
+        misses,
+        evictions,
+        size,
+    }
+}
+
+#[test]
+fn hits_and_misses() {
+    let mut cache = FormatCache::with_capacity(None);
+    assert_eq!(lookup(&mut cache, "<H"), Some(2));
+    assert_eq!(lookup(&mut cache, "<H"), Some(2));
+    assert_eq!(lookup(&mut cache, "<HH"), Some(3));
+    assert_eq!(cache.stats(), stats(1, 2, 0, 2));
+}
+
+#[test]
+fn failed_creations_are_not_cached() {
+    let mut cache: FormatCache<usize> = FormatCache::with_capacity(Some(1));
+    assert!(cache.get_or_insert_with("z", |_| None).is_none());
+    assert!(cache.get_or_insert_with("z", |_| None).is_none());
+    assert_eq!(cache.stats(), stats(0, 2, 0, 0));
+}
+
+#[test]
+fn least_recently_used_is_evicted() {
+    let mut cache = FormatCache::with_capacity(Some(2));
+    lookup(&mut cache, "a");
+    lookup(&mut cache, "b");
+    // Using `a` again leaves `b` as the least recently used.
+    lookup(&mut cache, "a");
+    lookup(&mut cache, "c");
+    assert_eq!(cache.stats(), stats(1, 3, 1, 2));
+    assert!(contains(&mut cache, "a"));
+    assert!(contains(&mut cache, "c"));
+    assert!(!contains(&mut cache, "b"));
+}
+
+#[test]
+fn shrinking_a_full_cache() {
+    let mut cache = FormatCache::with_capacity(Some(4));
+    for format in ["a", "b", "c", "d"] {
+        lookup(&mut cache, format);
+    }
+    lookup(&mut cache, "a");
+    cache.set_capacity(Some(2));
+    assert_eq!(cache.capacity(), Some(2));
+    assert_eq!(cache.stats(), stats(1, 4, 2, 2));
+    assert!(contains(&mut cache, "a"));
+    assert!(contains(&mut cache, "d"));
+
+    cache.set_capacity(None);
+    for format in ["e", "f", "g"] {
+        lookup(&mut cache, format);
+    }
+    assert_eq!(cache.stats().size, 5);
+    assert_eq!(cache.stats().evictions, 2);
+}
+
+#[test]
+fn clear_keeps_statistics() {
+    let mut cache = FormatCache::with_capacity(None);
+    lookup(&mut cache, "a");
+    lookup(&mut cache, "a");
+    cache.clear();
+    assert_eq!(cache.stats(), stats(1, 1, 0, 0));
+    lookup(&mut cache, "a");
+    assert_eq!(cache.stats(), stats(1, 2, 0, 1));
+}
@@ -0,0 +1,251 @@
+use godot_pack_core::{Column, Error, PackingDescriptor, Value, RECORDS_PER_THREAD};
+
+fn descriptor(format: &str) -> PackingDescriptor {
+    PackingDescriptor::sequence_from(format).expect("valid format")
+}
+
+/// Packs one record per row and concatenates them into a batch.
+fn batch(descriptor: &PackingDescriptor, rows: &[Vec<Value>]) -> Vec<u8> {
+    rows.iter()
+        .flat_map(|row| descriptor.pack(row.clone()).expect("packs"))
+        .collect()
+}
+
+#[test]
+fn unpack_columns() {
+    let descriptor = descriptor(">?bHIqfd2s 2x");
+    let rows = [
+        vec![
+            true.into(),
+            Value::Int(-1),
+            Value::Int(0xFFFF),
+            Value::Int(0xFFFF_FFFF),
+            Value::Int(-2),
+            Value::Float(0.5),
+            Value::Float(-1.25),
+            "ab".into(),
+        ],
+        vec![
+            false.into(),
+            Value::Int(7),
+            Value::Int(3),
+            Value::Int(4),
+            Value::Int(5),
+            Value::Float(6.0),
+            Value::Float(7.0),
+            "c".into(),
+        ],
+    ];
+    let columns = descriptor
+        .unpack_columns(&batch(&descriptor, &rows))
+        .unwrap();
+    assert_eq!(
+        columns,
+        [
+            Column::Bool(vec![true, false]),
+            Column::Int32(vec![-1, 7]),
+            Column::Int32(vec![0xFFFF, 3]),
+            Column::Int64(vec![0xFFFF_FFFF, 4]),
+            Column::Int64(vec![-2, 5]),
+            Column::Float32(vec![0.5, 6.0]),
+            Column::Float64(vec![-1.25, 7.0]),
+            Column::String(vec!["ab".into(), "c\0".into()]),
+        ]
+    );
+}
+
+#[test]
+fn columns_follow_alignment_and_seeks() {
+    let descriptor = descriptor("<B %4 I #12 H");
+    let rows = [
+        vec![Value::Int(1), Value::Int(2), Value::Int(3)],
+        vec![Value::Int(4), Value::Int(5), Value::Int(6)],
+    ];
+    let data = batch(&descriptor, &rows);
+    assert_eq!(data.len(), 28);
+    assert_eq!(
+        descriptor.unpack_columns(&data).unwrap(),
+        [
+            Column::Int32(vec![1, 4]),
+            Column::Int64(vec![2, 5]),
+            Column::Int32(vec![3, 6]),
+        ]
+    );
+}
+
+#[test]
+fn empty_batch() {
+    let columns = descriptor("<Hf").unpack_columns(&[]).unwrap();
+    assert_eq!(columns, [Column::Int32(vec![]), Column::Float32(vec![])]);
+}
+
+#[test]
+fn columns_errors() {
+    assert_eq!(
+        descriptor("<H").unpack_columns(&[0; 5]),
+        Err(Error::Truncated(6))
+    );
+    assert!(matches!(
+        descriptor("B {1: H}").unpack_columns(&[]),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        descriptor("B:a ?a(B)").unpack_columns(&[]),
+        Err(Error::Format(_))
+    ));
+    assert!(matches!(
+        descriptor("%4").unpack_columns(&[]),
+        Err(Error::Format(_))
+    ));
+
+    let descriptor = descriptor("<H W");
+    let mut data = batch(&descriptor, &[vec![Value::Int(1)], vec![Value::Int(2)]]);
+    assert!(descriptor.unpack_columns(&data).is_ok());
+    data[4] ^= 1;
+    assert!(matches!(
+        descriptor.unpack_columns(&data),
+        Err(Error::Unpack(_))
+    ));
+}
+
+#[test]
+fn pack_columns_matches_pack() {
+    let descriptor = descriptor(">B %4 f #12 3s C");
+    let columns = [
+        Column::Int32(vec![1, 2]),
+        Column::Float32(vec![0.5, -2.0]),
+        Column::String(vec!["abc".into(), "d".into()]),
+    ];
+    let packed = descriptor.pack_columns(&columns).unwrap();
+    let rows = [
+        vec![Value::Int(1), Value::Float(0.5), "abc".into()],
+        vec![Value::Int(2), Value::Float(-2.0), "d".into()],
+    ];
+    assert_eq!(packed, batch(&descriptor, &rows));
+
+    let unpacked = descriptor.unpack_columns(&packed).unwrap();
+    assert_eq!(unpacked[..2], columns[..2]);
+    assert_eq!(
+        unpacked[2],
+        Column::String(vec!["abc".into(), "d\0\0".into()])
+    );
+}
+
+#[test]
+fn pack_columns_converts_values() {
+    let descriptor = descriptor("<?Hd");
+    let columns = [
+        Column::Int32(vec![0, 2]),
+        Column::Float64(vec![3.9, 1.0]),
+        Column::Int64(vec![-1, 1]),
+    ];
+    assert_eq!(
+        descriptor
+            .unpack_columns(&descriptor.pack_columns(&columns).unwrap())
+            .unwrap(),
+        [
+            Column::Bool(vec![false, true]),
+            Column::Int32(vec![3, 1]),
+            Column::Float64(vec![-1.0, 1.0]),
+        ]
+    );
+}
+
+#[test]
+fn pack_columns_errors() {
+    let descriptor = descriptor("<BH");
+    let errors = [
+        descriptor.pack_columns(&[Column::Int32(vec![1])]),
+        descriptor.pack_columns(&[Column::Int32(vec![1]), Column::Int32(vec![1, 2])]),
+        descriptor.pack_columns(&[Column::Int32(vec![256]), Column::Int32(vec![1])]),
+        descriptor.pack_columns(&[Column::String(vec!["a".into()]), Column::Int32(vec![1])]),
+    ];
+    for error in errors {
+        assert!(matches!(error, Err(Error::Pack(_))), "{:?}", error);
+    }
+    assert_eq!(
+        descriptor.pack_columns(&[Column::Int32(vec![]), Column::Int64(vec![])]),
+        Ok(vec![])
+    );
+}
+
+#[test]
+fn parallel_batches_match_sequential_ones() {
+    let descriptor = descriptor("<I:id f:x 2s:tag W");
+    let count = RECORDS_PER_THREAD * 3 + 5;
+    let columns = [
+        Column::Int64((0..count as i64).collect()),
+        Column::Float32((0..count).map(|index| index as f32 * 0.5).collect()),
+        Column::String(
+            (0..count)
+                .map(|index| format!("{:02}", index % 100))
+                .collect(),
+        ),
+    ];
+    let packed = descriptor.pack_columns(&columns).unwrap();
+    for threads in [0, 2, 4, 64] {
+        assert_eq!(
+            descriptor.pack_columns_parallel(&columns, threads).unwrap(),
+            packed
+        );
+        assert_eq!(
+            descriptor
+                .unpack_columns_parallel(&packed, threads)
+                .unwrap(),
+            columns
+        );
+    }
+}
+
+#[test]
+fn parallel_errors_name_the_record() {
+    let descriptor = descriptor("<H W");
+    let count = RECORDS_PER_THREAD * 2;
+    let column = Column::Int32((0..count as i32).collect());
+    let mut data = descriptor.pack_columns(&[column]).unwrap();
+    data[(count - 1) * 4] ^= 1;
+    let Err(Error::Unpack(message)) = descriptor.unpack_columns_parallel(&data, 2) else {
+        panic!("corrupted record should be rejected");
+    };
+    assert!(
+        message.contains(&format!("record {}", count - 1)),
+        "{}",
+        message
+    );
+
+    // 5958 * 11 is the first value above `u16::MAX`, in the second thread's share.
+    let column = Column::Int32((0..count as i32).map(|index| index * 11).collect());
+    let Err(Error::Pack(message)) = descriptor.pack_columns_parallel(&[column], 2) else {
+        panic!("out of range value should be rejected");
+    };
+    assert!(message.contains("record 5958"), "{}", message);
+}
+
+#[test]
+fn repeated_parallel_batches() {
+    let descriptor = descriptor("<i:value");
+    let count = RECORDS_PER_THREAD * 4;
+    let column = Column::Int32((0..count as i32).collect());
+    let packed = descriptor
+        .pack_columns(std::slice::from_ref(&column))
+        .unwrap();
+    // Batches decoded one after another, and from several threads at once, share the same workers.
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| {
+                for _ in 0..25 {
+                    assert_eq!(
+                        descriptor.unpack_columns_parallel(&packed, 4).unwrap(),
+                        std::slice::from_ref(&column)
+                    );
+                    assert_eq!(
+                        descriptor
+                            .pack_columns_parallel(std::slice::from_ref(&column), 4)
+                            .unwrap(),
+                        packed
+                    );
+                }
+            });
+        }
+    });
+}
@@ -0,0 +1,99 @@
+use godot_pack_core::{Error, PackingDescriptor, Value};
+
+fn format_error(format: &str) -> Error {
+    PackingDescriptor::sequence_from(format).expect_err(format)
+}
+
+#[test]
+fn invalid_formats() {
+    let formats = [
+        "z",
+        "B:",
+        "x:name",
+        "B:a B:a",
+        "{1: B}",
+        "f {1: B}",
+        "B {1 B}",
+        "B {1: B, 1: H}",
+        "B {}",
+        "B {1: B",
+        "?missing(B)",
+        "f:x ?x(B)",
+        "B:a ?a(<B)",
+        "B:a ?a(B",
+        "%0",
+        "#-1",
+        "#0xZ",
+        "3B:rgb",
+        "f:.x",
+        "f:position.",
+        "f:position..x",
+        "70000B",
+        "3 B",
+        "B 2",
+        "2<B",
+    ];
+    for format in formats {
+        assert!(
+            matches!(format_error(format), Error::Format(_)),
+            "`{}` should be rejected",
+            format
+        );
+    }
+}
+
+#[test]
+fn truncated_data() {
+    let descriptor = PackingDescriptor::sequence_from("<HI").unwrap();
+    assert_eq!(descriptor.unpack(&[0; 5]), Err(Error::Truncated(6)));
+
+    // Variable layouts may only tell how much is needed once their discriminant is known.
+    let descriptor = PackingDescriptor::sequence_from("<B {1: I, _: B}").unwrap();
+    assert_eq!(descriptor.unpack(&[1, 0]), Err(Error::Truncated(5)));
+}
+
+#[test]
+fn values_out_of_range() {
+    let cases = [
+        ("b", Value::Int(128)),
+        ("B", Value::Int(-1)),
+        ("h", Value::Int(40000)),
+        ("H", Value::Int(70000)),
+        ("i", Value::Int(1 << 40)),
+        ("I", Value::Int(-1)),
+        ("B", Value::String("one".into())),
+        ("f", Value::Nil),
+        ("?", Value::String("yes".into())),
+    ];
+    for (format, value) in cases {
+        let descriptor = PackingDescriptor::sequence_from(format).unwrap();
+        assert!(
+            matches!(descriptor.pack(vec![value.clone()]), Err(Error::Pack(_))),
+            "`{}` should not fit `{}`",
+            value,
+            format
+        );
+    }
+}
+
+#[test]
+fn invalid_utf8() {
+    let descriptor = PackingDescriptor::sequence_from("2s").unwrap();
+    assert!(matches!(
+        descriptor.unpack(&[0xC3, 0x28]),
+        Err(Error::Unpack(_))
+    ));
+}
+
+#[test]
+fn missing_discriminant() {
+    let descriptor = PackingDescriptor::sequence_from("B {1: B}").unwrap();
+    assert!(matches!(descriptor.pack(vec![]), Err(Error::Pack(_))));
+
+    // An earlier integer field does not stand in for a discriminant without a value.
+    let descriptor = PackingDescriptor::sequence_from("<B:a B:tag {1: B, 5: H}").unwrap();
+    assert!(matches!(
+        descriptor.pack(vec![Value::Int(5)]),
+        Err(Error::Pack(_))
+    ));
+}
@@ -0,0 +1,184 @@
+use godot_pack_core::{Endianness, Error, FrameDecoder, Framing};
+
+const FRAMINGS: [Framing; 4] = [
+    Framing::LengthU16,
+    Framing::LengthU32,
+    Framing::Cobs,
+    Framing::Slip,
+];
+
+fn payloads() -> Vec<Vec<u8>> {
+    vec![
+        vec![],
+        vec![0],
+        vec![0, 0],
+        vec![0xC0, 0xDB, 0xDC, 0xDD],
+        (0..=255).collect(),
+        (1..=254).collect(),
+        (1..=255).collect(),
+        std::iter::repeat_n(7, 600).chain([0]).collect(),
+    ]
+}
+
+/// Decodes the single frame of `frame`, which must span it whole.
+fn decode(framing: Framing, frame: &[u8]) -> Result<Vec<u8>, Error> {
+    let (payload, size) = framing
+        .decode(frame, Endianness::BigEndian, usize::MAX)
+        .expect("complete frame");
+    assert_eq!(size, frame.len(), "span of {:?}", framing);
+    payload
+}
+
+#[test]
+fn round_trip() {
+    for framing in FRAMINGS {
+        for payload in payloads() {
+            // Empty delimited frames are skipped, since SLIP also starts frames with its delimiter.
+            if payload.is_empty() && framing == Framing::Slip {
+                continue;
+            }
+            let frame = framing.encode(&payload, Endianness::BigEndian).unwrap();
+            assert_eq!(decode(framing, &frame).unwrap(), payload, "{:?}", framing);
+        }
+    }
+}
+
+#[test]
+fn length_prefixes_follow_endianness() {
+    let frame = Framing::LengthU16
+        .encode(&[9], Endianness::BigEndian)
+        .unwrap();
+    assert_eq!(frame, [0, 1, 9]);
+    let frame = Framing::LengthU32
+        .encode(&[9], Endianness::LittleEndian)
+        .unwrap();
+    assert_eq!(frame, [1, 0, 0, 0, 9]);
+    assert!(Framing::LengthU16
+        .encode(&[0; 0x10000], Endianness::BigEndian)
+        .is_err());
+}
+
+#[test]
+fn cobs_runs() {
+    let frame = Framing::Cobs
+        .encode(&[0x11, 0, 0x22], Endianness::BigEndian)
+        .unwrap();
+    assert_eq!(frame, [2, 0x11, 2, 0x22, 0]);
+
+    // A run of 254 non-zero bytes fills a whole block, and a 255th starts another one.
+    let run: Vec<u8> = (1..=254).collect();
+    let frame = Framing::Cobs.encode(&run, Endianness::BigEndian).unwrap();
+    assert_eq!(frame[0], 0xFF);
+    assert_eq!(&frame[1..255], run.as_slice());
+    let minimal = [&[0xFF][..], &run, &[0]].concat();
+    assert_eq!(decode(Framing::Cobs, &minimal).unwrap(), run);
+
+    let run: Vec<u8> = (1..=255).collect();
+    let frame = Framing::Cobs.encode(&run, Endianness::BigEndian).unwrap();
+    assert_eq!(frame, [&[0xFF][..], &run[..254], &[2, 0xFF, 0]].concat());
+}
+
+#[test]
+fn malformed_frames() {
+    assert!(decode(Framing::Cobs, &[5, 1, 0]).is_err());
+    assert!(decode(Framing::Slip, &[0xC0, 0xDB, 1, 0xC0]).is_err());
+    assert!(decode(Framing::Slip, &[0xDB, 0xC0]).is_err());
+}
+
+#[test]
+fn incomplete_frames() {
+    let endianness = Endianness::BigEndian;
+    assert!(Framing::LengthU16
+        .decode(&[0], endianness, usize::MAX)
+        .is_none());
+    assert!(Framing::LengthU16
+        .decode(&[0, 2, 1], endianness, usize::MAX)
+        .is_none());
+    assert!(Framing::Cobs
+        .decode(&[2, 1], endianness, usize::MAX)
+        .is_none());
+    assert!(Framing::None
+        .decode(&[1, 2], endianness, usize::MAX)
+        .is_none());
+}
+
+/// Feeds `chunks` one at a time, collecting the frames found along with whether each one was valid.
+fn feed(decoder: &mut FrameDecoder, chunks: &[&[u8]]) -> Vec<Result<Vec<u8>, ()>> {
+    let mut frames = vec![];
+    for chunk in chunks {
+        decoder.feed(chunk);
+        while let Some(frame) = decoder.next_frame() {
+            frames.push(frame.map_err(|_| ()));
+        }
+    }
+    frames
+}
+
+#[test]
+fn frames_split_across_chunks() {
+    let mut decoder = FrameDecoder::new(Framing::Slip, Endianness::BigEndian);
+    let frames = feed(
+        &mut decoder,
+        &[&[0xC0, 1, 0xDB], &[0xDC, 0xC0, 0xC0, 2], &[0xC0]],
+    );
+    assert_eq!(frames, [Ok(vec![1, 0xC0]), Ok(vec![2])]);
+    assert!(decoder.buffered().is_empty());
+}
+
+#[test]
+fn too_long_length_prefixed_frames_are_skipped() {
+    let mut decoder = FrameDecoder::new(Framing::LengthU16, Endianness::BigEndian);
+    decoder.set_max_length(Some(4));
+    // The prefix itself is split, then the 10 bytes of the frame arrive over several chunks.
+    let frames = feed(
+        &mut decoder,
+        &[
+            &[0],
+            &[10, 1, 2, 3],
+            &[4, 5, 6],
+            &[7, 8, 9, 10, 0, 2],
+            &[11, 12],
+        ],
+    );
+    assert_eq!(frames, [Err(()), Ok(vec![11, 12])]);
+    assert!(decoder.buffered().is_empty());
+}
+
+#[test]
+fn too_long_delimited_frames_are_skipped() {
+    let mut decoder = FrameDecoder::new(Framing::Cobs, Endianness::BigEndian);
+    decoder.set_max_length(Some(4));
+    let frames = feed(
+        &mut decoder,
+        &[&[9, 1, 2, 3, 4, 5], &[6, 7, 8], &[9, 0, 2, 1], &[0]],
+    );
+    assert_eq!(frames, [Err(()), Ok(vec![1])]);
+
+    // Complete frames are rejected as well.
+    let frames = feed(&mut decoder, &[&[6, 1, 2, 3, 4, 5, 0, 2, 3, 0]]);
+    assert_eq!(frames, [Err(()), Ok(vec![3])]);
+}
+
+#[test]
+fn unlimited_frames() {
+    let mut decoder = FrameDecoder::new(Framing::LengthU32, Endianness::LittleEndian);
+    assert_eq!(decoder.max_length(), Some(FrameDecoder::DEFAULT_MAX_LENGTH));
+    decoder.set_max_length(None);
+    let payload = vec![1; FrameDecoder::DEFAULT_MAX_LENGTH + 1];
+    let frame = Framing::LengthU32
+        .encode(&payload, Endianness::LittleEndian)
+        .unwrap();
+    assert_eq!(feed(&mut decoder, &[&frame]), [Ok(payload)]);
+}
+
+#[test]
+fn unframed_records_are_consumed() {
+    let mut decoder = FrameDecoder::new(Framing::None, Endianness::BigEndian);
+    decoder.feed(&[1, 2, 3]);
+    assert!(decoder.next_frame().is_none());
+    decoder.consume(2);
+    decoder.feed(&[4]);
+    assert_eq!(decoder.buffered(), [3, 4]);
+    decoder.clear();
+    assert!(decoder.buffered().is_empty());
+}
@@ -0,0 +1,107 @@
+//! Arbitrary formats and data must only ever be rejected with an error, never panic.
+use godot_pack_core::{PackingDescriptor, Value};
+use proptest::prelude::*;
+
+/// Formats made of the pattern alphabet, which reach much deeper into the parser than random text.
+fn format() -> impl Strategy<Value = String> {
+    let token = prop_oneof![
+        Just("?".to_string()),
+        "[<>=@! csxbBhHiIlLqQfdWCA{}(),_:&%#?-]".prop_map(String::from),
+        "[0-9]{1,12}".prop_map(String::from),
+        "0x[0-9a-fA-F]{1,10}".prop_map(String::from),
+        ":[a-c]".prop_map(String::from),
+        "\\?[a-c](&[0-9]{1,3})?\\(".prop_map(String::from),
+    ];
+    prop::collection::vec(token, 0..40).prop_map(|tokens| tokens.concat())
+}
+
+fn value() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Nil),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::Int),
+        any::<f64>().prop_map(Value::Float),
+        ".{0,8}".prop_map(Value::String),
+        "[A-Z]{1,4}".prop_map(Value::Name),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn any_text_parses_or_fails(format in ".{0,64}") {
+        let _ = PackingDescriptor::sequence_from(&format);
+    }
+
+    #[test]
+    fn any_pattern_parses_or_fails(format in format()) {
+        let _ = PackingDescriptor::sequence_from(&format);
+    }
+
+    #[test]
+    fn any_data_unpacks_or_fails(
+        format in format(),
+        data in prop::collection::vec(any::<u8>(), 0..256),
+    ) {
+        if let Ok(descriptor) = PackingDescriptor::sequence_from(&format) {
+            let _ = descriptor.unpack(&data);
+            let _ = descriptor.inspect(&data);
+            let _ = descriptor.unpack_columns(&data);
+        }
+    }
+
+    /// `inspect` always walks the fields, while fixed layouts unpack through their compiled plan.
+    #[test]
+    fn compiled_and_walked_unpacks_agree(
+        format in format(),
+        data in prop::collection::vec(any::<u8>(), 0..256),
+    ) {
+        if let Ok(descriptor) = PackingDescriptor::sequence_from(&format) {
+            if let Ok((values, _)) = descriptor.unpack(&data) {
+                let (spans, result) = descriptor.inspect(&data);
+                prop_assert!(result.is_ok());
+                let compiled: Vec<_> = values.into_iter().filter(|value| !value.is_nil()).collect();
+                let walked: Vec<_> = spans
+                    .into_iter()
+                    .filter(|span| span.has_value)
+                    .map(|span| span.value)
+                    .collect();
+                // Debug output, so that NaN floats compare equal.
+                prop_assert_eq!(format!("{:?}", compiled), format!("{:?}", walked));
+            }
+        }
+    }
+
+    #[test]
+    fn any_values_pack_or_fail(
+        format in format(),
+        values in prop::collection::vec(value(), 0..16),
+    ) {
+        if let Ok(descriptor) = PackingDescriptor::sequence_from(&format) {
+            if let Ok(packed) = descriptor.pack(values) {
+                prop_assert!(packed.len() >= descriptor.size());
+                let _ = descriptor.unpack(&packed);
+            }
+        }
+    }
+}
+
+#[test]
+fn deep_nesting_is_rejected() {
+    let format = "B:a ?a(".repeat(10_000);
+    assert!(PackingDescriptor::sequence_from(&format).is_err());
+}
+
+#[test]
+fn huge_offsets_are_rejected() {
+    for format in [
+        "#99999999999",
+        "B %0x7FFFFFFFFFFFFFFF",
+        "B #9223372036854775807 B",
+    ] {
+        assert!(
+            PackingDescriptor::sequence_from(format).is_err(),
+            "`{}` should be rejected",
+            format
+        );
+    }
+}
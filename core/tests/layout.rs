@@ -0,0 +1,282 @@
+use godot_pack_core::{Error, PackingDescriptor, Value, ValueKind};
+
+fn descriptor(format: &str) -> PackingDescriptor {
+    PackingDescriptor::sequence_from(format).expect("valid format")
+}
+
+fn ints(values: &[i64]) -> Vec<Value> {
+    values.iter().copied().map(Value::Int).collect()
+}
+
+#[test]
+fn union_selects_branch() {
+    let descriptor = descriptor("<B:tag {1: H, 2: 3s, _: x}");
+    assert_eq!(descriptor.size(), 2);
+
+    let packed = descriptor.pack(ints(&[1, 0x0102])).unwrap();
+    assert_eq!(packed, [1, 2, 1]);
+    assert_eq!(descriptor.unpack(&packed).unwrap(), (ints(&[1, 0x0102]), 3));
+
+    let values = vec![Value::Int(2), "abc".into()];
+    let packed = descriptor.pack(values.clone()).unwrap();
+    assert_eq!(packed, b"\x02abc");
+    assert_eq!(descriptor.unpack(&packed).unwrap(), (values, 4));
+
+    let packed = descriptor.pack(ints(&[9])).unwrap();
+    assert_eq!(packed, [9, 0]);
+    assert_eq!(descriptor.unpack(&packed).unwrap(), (ints(&[9]), 2));
+}
+
+#[test]
+fn union_branch_endianness() {
+    let descriptor = descriptor("<B {0x10: >H}");
+    assert_eq!(descriptor.pack(ints(&[16, 0x0102])).unwrap(), [16, 1, 2]);
+}
+
+#[test]
+fn union_without_fallback() {
+    let descriptor = descriptor("B {1: B}");
+    assert!(descriptor.pack(ints(&[2, 0])).is_err());
+    assert!(descriptor.unpack(&[2, 0]).is_err());
+}
+
+#[test]
+fn condition_present_and_absent() {
+    let descriptor = descriptor("<B:flags ?flags&0x4(H) B");
+    assert_eq!(descriptor.size(), 2);
+
+    let present = ints(&[4, 0x0102, 7]);
+    let packed = descriptor.pack(present.clone()).unwrap();
+    assert_eq!(packed, [4, 2, 1, 7]);
+    assert_eq!(descriptor.unpack(&packed).unwrap(), (present, 4));
+
+    let packed = descriptor.pack(ints(&[1, 7])).unwrap();
+    assert_eq!(packed, [1, 7]);
+    assert_eq!(
+        descriptor.unpack(&packed).unwrap().0,
+        vec![Value::Int(1), Value::Nil, Value::Int(7)]
+    );
+    let packed_with_nil = descriptor
+        .pack(vec![Value::Int(1), Value::Nil, Value::Int(7)])
+        .unwrap();
+    assert_eq!(packed_with_nil, packed);
+}
+
+#[test]
+fn condition_without_mask() {
+    let descriptor = descriptor("B:count ?count(B)");
+    assert_eq!(descriptor.pack(ints(&[0])).unwrap(), [0]);
+    assert_eq!(descriptor.pack(ints(&[2, 5])).unwrap(), [2, 5]);
+}
+
+#[test]
+fn union_within_condition() {
+    // Branches may differ in their number of values, which an absent group could not match.
+    let error = PackingDescriptor::sequence_from("<B:t ?t&2(B:u {1: H, _: B})").unwrap_err();
+    assert!(matches!(error, Error::Format(_)), "{:?}", error);
+}
+
+#[test]
+fn nested_conditions_keep_their_values() {
+    let descriptor = descriptor("<B:a ?a(B:b ?b(B B)) B");
+    let nil = Value::Nil;
+    assert_eq!(
+        descriptor.unpack(&[0, 9]).unwrap().0,
+        vec![
+            Value::Int(0),
+            nil.clone(),
+            nil.clone(),
+            nil.clone(),
+            Value::Int(9)
+        ]
+    );
+    assert_eq!(
+        descriptor.unpack(&[1, 0, 9]).unwrap().0,
+        vec![
+            Value::Int(1),
+            Value::Int(0),
+            nil.clone(),
+            nil.clone(),
+            Value::Int(9)
+        ]
+    );
+    assert_eq!(
+        descriptor.unpack(&[1, 1, 2, 3, 9]).unwrap().0,
+        ints(&[1, 1, 2, 3, 9])
+    );
+    let values = vec![Value::Int(0), nil.clone(), nil.clone(), nil, Value::Int(9)];
+    assert_eq!(descriptor.pack(values).unwrap(), [0, 9]);
+}
+
+#[test]
+fn bool_is_not_a_condition() {
+    let descriptor = descriptor("B:count ?:flag");
+    assert_eq!(descriptor.size(), 2);
+}
+
+#[test]
+fn alignment() {
+    let descriptor = descriptor("<B %4 I");
+    assert_eq!(descriptor.size(), 8);
+    let packed = descriptor.pack(ints(&[1, 2])).unwrap();
+    assert_eq!(packed, [1, 0, 0, 0, 2, 0, 0, 0]);
+    assert_eq!(descriptor.unpack(&packed).unwrap().0, ints(&[1, 2]));
+}
+
+#[test]
+fn seek() {
+    let descriptor = descriptor("B #0x8 B #2 B");
+    assert_eq!(descriptor.size(), 9);
+    let packed = descriptor.pack(ints(&[1, 2, 3])).unwrap();
+    assert_eq!(packed, [1, 0, 3, 0, 0, 0, 0, 0, 2]);
+    assert_eq!(descriptor.unpack(&packed).unwrap(), (ints(&[1, 2, 3]), 9));
+}
+
+#[test]
+fn seek_back_consumes_everything_read() {
+    let descriptor = descriptor("<B {1: I, 2: B} #0 B");
+    let packed = [1, 7, 0, 0, 0];
+    assert_eq!(descriptor.unpack(&packed).unwrap(), (ints(&[1, 7, 1]), 5));
+}
+
+#[test]
+fn size_of_seeking_union_branches() {
+    // The first branch ends before the second one but reaches further.
+    let descriptor = descriptor("<B {1: #10 #1, 2: H}");
+    assert_eq!(descriptor.size(), 3);
+    assert_eq!(descriptor.unpack(&[2, 1, 0]).unwrap(), (ints(&[2, 1]), 3));
+    let mut packed = vec![0; 10];
+    packed[0] = 1;
+    assert_eq!(descriptor.unpack(&packed).unwrap(), (ints(&[1]), 10));
+}
+
+#[test]
+fn checksums() {
+    // Check values of "123456789" for each algorithm.
+    let cases = [
+        ("<9s W", vec![0xB1, 0x29]),
+        (">9s W", vec![0x29, 0xB1]),
+        (">9s C", vec![0xCB, 0xF4, 0x39, 0x26]),
+        (">9s A", vec![0x09, 0x1E, 0x01, 0xDE]),
+    ];
+    for (format, checksum) in cases {
+        let descriptor = descriptor(format);
+        let packed = descriptor.pack(vec!["123456789".into()]).unwrap();
+        assert_eq!(
+            &packed[9..],
+            checksum.as_slice(),
+            "checksum of `{}`",
+            format
+        );
+        assert_eq!(
+            descriptor.unpack(&packed).unwrap().0,
+            vec!["123456789".into()]
+        );
+
+        let mut corrupted = packed.clone();
+        corrupted[0] ^= 1;
+        assert!(descriptor.unpack(&corrupted).is_err());
+    }
+}
+
+#[test]
+fn checksum_covers_the_final_record() {
+    let descriptor = descriptor("<B #4 C #1 B");
+    let packed = descriptor.pack(ints(&[1, 2])).unwrap();
+    assert_eq!(&packed[..4], [1, 2, 0, 0]);
+    assert!(descriptor.unpack(&packed).is_ok());
+}
+
+#[test]
+fn enums() {
+    let mut descriptor = descriptor("B:kind {1: H, _: B}");
+    let table = [("MOVE".to_string(), 1), ("STOP".to_string(), 2)];
+    descriptor.set_enum("kind", table).unwrap();
+
+    let packed = descriptor
+        .pack(vec![Value::Name("MOVE".into()), Value::Int(3)])
+        .unwrap();
+    assert_eq!(packed, [1, 3, 0]);
+    assert_eq!(
+        descriptor.unpack(&packed).unwrap().0,
+        vec![Value::Name("MOVE".into()), Value::Int(3)]
+    );
+    assert_eq!(
+        descriptor.unpack(&[2, 0]).unwrap().0,
+        vec![Value::Name("STOP".into()), Value::Int(0)]
+    );
+    assert_eq!(descriptor.unpack(&[5, 0]).unwrap().0, ints(&[5, 0]));
+    assert!(descriptor.pack(vec!["JUMP".into()]).is_err());
+
+    descriptor.set_strict(true);
+    assert!(descriptor.is_strict());
+    assert!(descriptor.unpack(&[5, 0]).is_err());
+    assert!(descriptor.pack(ints(&[5])).is_err());
+    assert!(descriptor.pack(vec![Value::Int(2)]).is_ok());
+}
+
+#[test]
+fn enums_need_an_integer_field() {
+    let mut descriptor = descriptor("B:kind f:speed");
+    assert!(descriptor.set_enum("speed", []).is_err());
+    assert!(descriptor.set_enum("missing", []).is_err());
+}
+
+#[test]
+fn inspect() {
+    let descriptor = descriptor("<H:id 2x W f:x");
+    let packed = descriptor
+        .pack(vec![Value::Int(7), Value::Float(1.0)])
+        .unwrap();
+    let (spans, result) = descriptor.inspect(&packed);
+    assert!(result.is_ok());
+    let summary: Vec<_> = spans
+        .iter()
+        .map(|span| (span.name.as_deref(), span.kind, span.offset, span.length))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            (Some("id"), "uint16", 0, 2),
+            (None, "padding", 2, 2),
+            (None, "crc16", 4, 2),
+            (Some("x"), "float", 6, 4),
+        ]
+    );
+    assert_eq!(spans[0].value, Value::Int(7));
+    assert!(spans[0].has_value && !spans[1].has_value && !spans[2].has_value);
+}
+
+#[test]
+fn inspect_truncated_data() {
+    let (spans, result) = descriptor("<HH").inspect(&[1, 0, 2]);
+    assert_eq!(spans.len(), 1);
+    assert!(result.is_err());
+}
+
+#[test]
+fn properties() {
+    let descriptor = descriptor("<B:kind {1: f:x, 2: 4s:x, _: x} ?kind&1(H:extra) ?:flag");
+    assert_eq!(
+        descriptor.properties().unwrap(),
+        [
+            ("kind".to_string(), Some(ValueKind::Int)),
+            ("x".to_string(), None),
+            ("extra".to_string(), None),
+            ("flag".to_string(), Some(ValueKind::Bool)),
+        ]
+    );
+    assert!(self::descriptor("B:named B").properties().is_err());
+}
+
+#[test]
+fn property_paths() {
+    let descriptor = descriptor("<B:flags f:position.x f:position.y ?flags(f:velocity.x)");
+    let names: Vec<_> = descriptor
+        .properties()
+        .unwrap()
+        .into_iter()
+        .map(|(name, _)| name)
+        .collect();
+    assert_eq!(names, ["flags", "position.x", "position.y", "velocity.x"]);
+}
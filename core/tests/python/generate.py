@@ -0,0 +1,114 @@
+"""Generates corpus.tsv, the expected results of Python's struct module checked by python_compat.rs.
+
+Each line holds a format, its size, the values and the packed bytes in hexadecimal. Values are written as
+`?:0|1` for booleans, `i:n` for integers, `f:repr` for floats, `s:hex` for the bytes of `s` and `c:hex` for
+the byte of `c`, separated by commas; they are the result of unpacking, so floats are already rounded to
+their field's precision.
+
+Only standard sizes are used (`<`, `>`, `!`, `=`): native mode alignment is not emulated. `=` is native byte
+order, so the corpus must be generated on a little-endian machine.
+
+Run with `python3 core/tests/python/generate.py` from the repository root.
+"""
+
+import os
+import re
+import struct
+
+ORDERS = "<>!="
+
+CODES = {
+    "?": [True, False],
+    "c": [b"A", b"\x00", b"\xff"],
+    "b": [-128, 127, 0, -1],
+    "B": [0, 255],
+    "h": [-32768, 32767, -2],
+    "H": [0, 65535, 0x1234],
+    "i": [-(2**31), 2**31 - 1, -1],
+    "I": [0, 2**32 - 1, 0xDEADBEEF],
+    "l": [-(2**31), 2**31 - 1],
+    "L": [0, 2**32 - 1],
+    "q": [-(2**63), 2**63 - 1, -1],
+    "Q": [0, 2**64 - 1, 0x0102030405060708],
+    "f": [0.0, 1.5, -0.1, 3.4e38, float("inf"), float("-inf")],
+    "d": [0.0, -2.5, 0.1, 1e308, 5e-324, float("inf")],
+}
+
+CASES = [
+    # Repeat counts.
+    ("<3B", [1, 2, 3]),
+    ("<2h", [-1, 2]),
+    (">4I", [1, 2, 3, 4]),
+    ("<2?", [True, False]),
+    ("<3c", [b"a", b"b", b"c"]),
+    ("<0B", []),
+    ("<2d", [0.5, -0.25]),
+    (">2f", [1.0, 2.0]),
+    ("<10B", list(range(10))),
+    ("!0BH", [7]),
+    # Strings.
+    ("<5s", [b"hello"]),
+    ("<5s", [b"hi"]),
+    ("<s", [b"a"]),
+    ("<10s", [b""]),
+    ("<3s", [b"abcdef"]),
+    ("<2s3s", [b"ab", b"cde"]),
+    ("<0s", [b""]),
+    ("<B0sH", [1, b"", 2]),
+    # Characters beyond ASCII.
+    ("<c", [b"\x80"]),
+    ("<3c", [b"\xe9", b"a", b"\x7f"]),
+    ("<2sc", [b"ok", b"\xc3"]),
+    # Padding.
+    ("<x", []),
+    ("<3x", []),
+    ("<B3xH", [1, 2]),
+    (">xBxH", [1, 0x0102]),
+    ("<2xB", [9]),
+    ("<h2x", [-3]),
+    ("<0x", []),
+    ("<B0xH", [1, 2]),
+    # Mixed layouts and whitespace.
+    ("<HI2sd?", [1, 2, b"ok", 0.5, True]),
+    (">3bQ", [-1, 0, 1, 2**63]),
+    ("!4sHH", [b"GPAK", 1, 2]),
+    ("<?b?B", [True, -5, False, 250]),
+    ("< H I", [1, 2]),
+    ("<3B 2x H", [1, 2, 3, 4]),
+]
+
+
+def codes(fmt):
+    """The code of every value of `fmt`."""
+    result = []
+    for count, code in re.findall(r"(\d*)([a-zA-Z?])", fmt):
+        if code != "x":
+            result += [code] * (1 if code == "s" else int(count or 1))
+    return result
+
+
+def encode(value, code):
+    if code == "c":
+        return "c:%s" % value.hex()
+    if isinstance(value, bool):
+        return "?:%d" % value
+    if isinstance(value, int):
+        return "i:%d" % value
+    if isinstance(value, float):
+        return "f:%r" % value
+    return "s:%s" % value.hex()
+
+
+def lines():
+    cases = [(order + code, [value]) for order in ORDERS for code, values in CODES.items() for value in values]
+    for fmt, values in cases + CASES:
+        packed = struct.pack(fmt, *values)
+        unpacked = struct.unpack(fmt, packed)
+        values = ",".join(map(encode, unpacked, codes(fmt)))
+        yield "\t".join([fmt, str(struct.calcsize(fmt)), values, packed.hex()])
+
+
+if __name__ == "__main__":
+    path = os.path.join(os.path.dirname(os.path.abspath(__file__)), "corpus.tsv")
+    with open(path, "w") as corpus:
+        corpus.write("\n".join(lines()) + "\n")
@@ -0,0 +1,61 @@
+//! Checks sizes, packed bytes and unpacked values against Python's `struct` module, as recorded in
+//! `python/corpus.tsv` by `python/generate.py`.
+use godot_pack_core::{PackingDescriptor, Value};
+
+const CORPUS: &str = include_str!("python/corpus.tsv");
+
+fn parse_value(token: &str) -> Value {
+    let (kind, text) = token.split_once(':').expect("typed value");
+    match kind {
+        "?" => Value::Bool(text == "1"),
+        // Python integers are unbounded, `Q` values above `i64::MAX` wrap around.
+        "i" => Value::Int(text.parse::<i128>().expect("integer") as i64),
+        "f" => Value::Float(text.parse().expect("float")),
+        "s" => Value::String(String::from_utf8(parse_hex(text)).expect("UTF-8 bytes")),
+        // `c` maps its byte to the code point of the same value, as Latin-1 does.
+        "c" => Value::String(
+            parse_hex(text)
+                .iter()
+                .map(|byte| char::from(*byte))
+                .collect(),
+        ),
+        _ => panic!("unknown value kind `{}`", kind),
+    }
+}
+
+fn parse_hex(text: &str) -> Vec<u8> {
+    (0..text.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&text[index..index + 2], 16).expect("hex byte"))
+        .collect()
+}
+
+#[test]
+fn python_struct_corpus() {
+    let mut checked = 0;
+    for line in CORPUS.lines().filter(|line| !line.is_empty()) {
+        let columns: Vec<&str> = line.split('\t').collect();
+        let [format, size, values, bytes] = columns[..] else {
+            panic!("malformed corpus line `{}`", line);
+        };
+        if format.starts_with('=') && cfg!(target_endian = "big") {
+            continue;
+        }
+        let size: usize = size.parse().unwrap();
+        let values: Vec<Value> = values
+            .split(',')
+            .filter(|token| !token.is_empty())
+            .map(parse_value)
+            .collect();
+        let bytes = parse_hex(bytes);
+
+        let descriptor = PackingDescriptor::sequence_from(format).expect(format);
+        assert_eq!(descriptor.size(), size, "size of `{}`", format);
+        let packed = descriptor.pack(values.clone()).expect(format);
+        assert_eq!(packed, bytes, "bytes of `{}` {:?}", format, values);
+        let unpacked = descriptor.unpack(&bytes).expect(format);
+        assert_eq!(unpacked, (values, size), "values of `{}`", format);
+        checked += 1;
+    }
+    assert!(checked > 100);
+}
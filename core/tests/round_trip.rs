@@ -0,0 +1,224 @@
+use godot_pack_core::{PackingDescriptor, Value};
+
+fn descriptor(format: &str) -> PackingDescriptor {
+    PackingDescriptor::sequence_from(format).expect("valid format")
+}
+
+/// Packs `values` with `format`, checks the bytes, then unpacks them back into the same values.
+fn round_trip(format: &str, values: Vec<Value>, bytes: &[u8]) {
+    let descriptor = descriptor(format);
+    let packed = descriptor.pack(values.clone()).expect("packs");
+    assert_eq!(packed, bytes, "bytes of `{}`", format);
+    assert_eq!(descriptor.size(), bytes.len(), "size of `{}`", format);
+    let (unpacked, size) = descriptor.unpack(&packed).expect("unpacks");
+    assert_eq!(unpacked, values, "values of `{}`", format);
+    assert_eq!(size, bytes.len());
+}
+
+#[test]
+fn bool() {
+    round_trip("?", vec![true.into()], &[1]);
+    round_trip("?", vec![false.into()], &[0]);
+}
+
+#[test]
+fn character() {
+    round_trip("c", vec!["A".into()], b"A");
+}
+
+#[test]
+fn string() {
+    round_trip("5s", vec!["hello".into()], b"hello");
+    round_trip("s", vec!["h".into()], b"h");
+}
+
+#[test]
+fn string_is_truncated_or_zero_filled() {
+    let descriptor = descriptor("4s");
+    assert_eq!(descriptor.pack(vec!["toolong".into()]).unwrap(), b"tool");
+    assert_eq!(descriptor.pack(vec!["ab".into()]).unwrap(), b"ab\0\0");
+}
+
+#[test]
+fn signed_char() {
+    round_trip("b", vec![Value::Int(-2)], &[0xFE]);
+    round_trip("b", vec![Value::Int(127)], &[0x7F]);
+}
+
+#[test]
+fn unsigned_char() {
+    round_trip("B", vec![Value::Int(255)], &[0xFF]);
+}
+
+#[test]
+fn short() {
+    round_trip("<h", vec![Value::Int(-2)], &[0xFE, 0xFF]);
+    round_trip(">h", vec![Value::Int(-2)], &[0xFF, 0xFE]);
+}
+
+#[test]
+fn unsigned_short() {
+    round_trip("<H", vec![Value::Int(0x1234)], &[0x34, 0x12]);
+    round_trip(">H", vec![Value::Int(0x1234)], &[0x12, 0x34]);
+}
+
+#[test]
+fn int() {
+    round_trip("<i", vec![Value::Int(-1)], &[0xFF; 4]);
+    round_trip(">i", vec![Value::Int(0x01020304)], &[1, 2, 3, 4]);
+}
+
+#[test]
+fn unsigned_int() {
+    round_trip(
+        "<I",
+        vec![Value::Int(0xDEADBEEF)],
+        &[0xEF, 0xBE, 0xAD, 0xDE],
+    );
+}
+
+#[test]
+fn long() {
+    round_trip("<l", vec![Value::Int(-5)], &[0xFB, 0xFF, 0xFF, 0xFF]);
+}
+
+#[test]
+fn unsigned_long() {
+    round_trip(
+        ">L",
+        vec![Value::Int(0xDEADBEEF)],
+        &[0xDE, 0xAD, 0xBE, 0xEF],
+    );
+}
+
+#[test]
+fn long_long() {
+    round_trip(
+        "<q",
+        vec![Value::Int(i64::MIN)],
+        &[0, 0, 0, 0, 0, 0, 0, 0x80],
+    );
+    round_trip(">q", vec![Value::Int(1)], &[0, 0, 0, 0, 0, 0, 0, 1]);
+}
+
+#[test]
+fn unsigned_long_long() {
+    round_trip(">Q", vec![Value::Int(1)], &[0, 0, 0, 0, 0, 0, 0, 1]);
+}
+
+#[test]
+fn unsigned_long_long_wraps_around() {
+    round_trip("<Q", vec![Value::Int(-1)], &[0xFF; 8]);
+}
+
+#[test]
+fn float() {
+    round_trip("<f", vec![Value::Float(1.5)], &1.5f32.to_le_bytes());
+    round_trip(">f", vec![Value::Float(-0.25)], &(-0.25f32).to_be_bytes());
+}
+
+#[test]
+fn double() {
+    round_trip(
+        "<d",
+        vec![Value::Float(1.0 / 3.0)],
+        &(1.0f64 / 3.0).to_le_bytes(),
+    );
+    round_trip(">d", vec![Value::Float(-2.5)], &(-2.5f64).to_be_bytes());
+}
+
+#[test]
+fn padding() {
+    round_trip("B3xB", vec![Value::Int(1), Value::Int(2)], &[1, 0, 0, 0, 2]);
+    round_trip("xBx", vec![Value::Int(7)], &[0, 7, 0]);
+}
+
+#[test]
+fn whitespace_and_names() {
+    round_trip(
+        "< B:kind  H:length ",
+        vec![Value::Int(1), Value::Int(2)],
+        &[1, 2, 0],
+    );
+}
+
+#[test]
+fn big_endian_runs() {
+    // Named and unnamed fields of one type form a single run, long enough to be swapped in blocks.
+    let values: Vec<Value> = (1..=9)
+        .map(|value| Value::Int(value * 0x0101 + 1))
+        .collect();
+    let bytes: Vec<u8> = (1..=9u16)
+        .flat_map(|value| (value * 0x0101 + 1).to_be_bytes())
+        .collect();
+    round_trip(">H:first H:second 7H", values, &bytes);
+    let values: Vec<Value> = (1..=5)
+        .map(|value| Value::Float(value as f64 * 0.5))
+        .collect();
+    let bytes: Vec<u8> = (1..=5)
+        .flat_map(|value| (value as f64 * 0.5).to_be_bytes())
+        .collect();
+    round_trip(">d:x d:y 3d", values, &bytes);
+}
+
+#[test]
+fn every_code_together() {
+    let values = vec![
+        "ab".into(),
+        "c".into(),
+        true.into(),
+        Value::Int(-1),
+        Value::Int(1),
+        Value::Int(-2),
+        Value::Int(2),
+        Value::Int(-3),
+        Value::Int(3),
+        Value::Int(-4),
+        Value::Int(4),
+        Value::Int(-5),
+        Value::Int(5),
+        Value::Float(0.5),
+        Value::Float(0.25),
+    ];
+    let descriptor = descriptor("<2sc?bBhHiIlLqQfd");
+    assert_eq!(
+        descriptor.size(),
+        2 + 1 + 1 + 1 + 1 + 2 + 2 + 4 * 4 + 8 + 8 + 4 + 8
+    );
+    let packed = descriptor.pack(values.clone()).unwrap();
+    assert_eq!(descriptor.unpack(&packed).unwrap().0, values);
+}
+
+#[test]
+fn endianness_defaults_to_native() {
+    let native = descriptor("H").pack(vec![Value::Int(0x0102)]).unwrap();
+    assert_eq!(native, 0x0102u16.to_ne_bytes());
+    assert_eq!(
+        descriptor("!H").pack(vec![Value::Int(0x0102)]).unwrap(),
+        [1, 2]
+    );
+    assert_eq!(
+        descriptor("=H").pack(vec![Value::Int(0x0102)]).unwrap(),
+        native
+    );
+}
+
+#[test]
+fn missing_values_are_zeroed() {
+    assert_eq!(
+        descriptor("<BH").pack(vec![Value::Int(1)]).unwrap(),
+        [1, 0, 0]
+    );
+}
+
+#[test]
+fn values_are_converted() {
+    let descriptor = descriptor("<B?d");
+    let packed = descriptor
+        .pack(vec![Value::Float(3.9), Value::Int(2), Value::Bool(true)])
+        .unwrap();
+    assert_eq!(
+        descriptor.unpack(&packed).unwrap().0,
+        vec![Value::Int(3), Value::Bool(true), Value::Float(1.0)]
+    );
+}
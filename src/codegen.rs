@@ -0,0 +1,173 @@
+use godot::classes::ClassDb;
+use godot_pack_core::ValueKind;
+
+/// Names a generated class cannot use: GDScript keywords and constants, built-in types, and the members the
+/// class defines itself.
+const RESERVED: &[&str] = &[
+    "and",
+    "as",
+    "assert",
+    "await",
+    "break",
+    "breakpoint",
+    "class",
+    "class_name",
+    "const",
+    "continue",
+    "elif",
+    "else",
+    "enum",
+    "extends",
+    "false",
+    "for",
+    "func",
+    "if",
+    "in",
+    "is",
+    "match",
+    "namespace",
+    "not",
+    "null",
+    "or",
+    "pass",
+    "preload",
+    "return",
+    "self",
+    "signal",
+    "static",
+    "super",
+    "trait",
+    "true",
+    "var",
+    "void",
+    "when",
+    "while",
+    "yield",
+    "PI",
+    "TAU",
+    "INF",
+    "NAN",
+    "Variant",
+    "bool",
+    "int",
+    "float",
+    "String",
+    "StringName",
+    "NodePath",
+    "Vector2",
+    "Vector2i",
+    "Vector3",
+    "Vector3i",
+    "Vector4",
+    "Vector4i",
+    "Rect2",
+    "Rect2i",
+    "Transform2D",
+    "Transform3D",
+    "Plane",
+    "Quaternion",
+    "AABB",
+    "Basis",
+    "Projection",
+    "Color",
+    "RID",
+    "Object",
+    "Callable",
+    "Signal",
+    "Dictionary",
+    "Array",
+    "PackedByteArray",
+    "PackedInt32Array",
+    "PackedInt64Array",
+    "PackedFloat32Array",
+    "PackedFloat64Array",
+    "PackedStringArray",
+    "PackedVector2Array",
+    "PackedVector3Array",
+    "PackedVector4Array",
+    "PackedColorArray",
+    "FORMAT",
+    "_pack",
+    "to_bytes",
+    "from_bytes",
+];
+
+/// Whether `name` can be used as a GDScript identifier.
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|first| first.is_alphabetic() || first == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Whether `name` is an identifier other than a keyword, a built-in type or a generated member.
+fn is_unreserved(name: &str) -> bool {
+    is_identifier(name) && !RESERVED.contains(&name)
+}
+
+/// Whether `name` can be used as the name of a generated class, without hiding a native or extension class.
+pub(crate) fn is_class_name(name: &str) -> bool {
+    is_unreserved(name) && !ClassDb::singleton().class_exists(name)
+}
+
+/// Whether `name` can be used as a property of the generated class `class_name`, without clashing with
+/// the members it inherits from `RefCounted`.
+pub(crate) fn is_property_name(name: &str, class_name: &str) -> bool {
+    let class_db = ClassDb::singleton();
+    is_unreserved(name)
+        && name != class_name
+        && !class_db.class_has_method("RefCounted", name)
+        && !class_db.class_has_signal("RefCounted", name)
+        && name != "script"
+}
+
+/// Type of values of `kind` in GDScript.
+fn gdscript_type(kind: ValueKind) -> &'static str {
+    match kind {
+        ValueKind::Bool => "bool",
+        ValueKind::Int => "int",
+        ValueKind::Float => "float",
+        ValueKind::String => "String",
+    }
+}
+
+/// Writes a GDScript class holding `properties`, converted from and to bytes by a `Pack` of `format`.
+pub(crate) fn gdscript_class(
+    class_name: &str,
+    format: &str,
+    properties: &[(String, Option<ValueKind>)],
+) -> String {
+    let literal = format
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t");
+    let mut source = format!(
+        "class_name {class_name}\n\
+         extends RefCounted\n\
+         \n\
+         const FORMAT := \"{literal}\"\n\
+         static var _pack: Pack = Pack.from(FORMAT)\n\
+         \n"
+    );
+    for (name, kind) in properties {
+        match kind {
+            Some(kind) => source.push_str(&format!("var {name}: {}\n", gdscript_type(*kind))),
+            None => source.push_str(&format!("var {name}\n")),
+        }
+    }
+    source.push_str(&format!(
+        "\n\
+         func to_bytes() -> PackedByteArray:\n\
+         \tvar bytes = _pack.pack_object(self)\n\
+         \treturn bytes if bytes != null else PackedByteArray()\n\
+         \n\
+         static func from_bytes(bytes: PackedByteArray) -> {class_name}:\n\
+         \tvar result := {class_name}.new()\n\
+         \tif not _pack.unpack_into(result, bytes):\n\
+         \t\treturn null\n\
+         \treturn result\n"
+    ));
+    source
+}
@@ -0,0 +1,135 @@
+use godot::classes::RefCounted;
+use godot::prelude::*;
+
+use godot_pack_core::{self as pack_core, FrameDecoder};
+
+use crate::framing::Framing;
+use crate::pack::{Pack, UnpackError};
+
+/// Incrementally decodes records out of a byte stream delivered in arbitrary chunks, as TCP does.
+/// Bytes are buffered by `feed` until whole records are available, each of them being both returned and
+/// emitted through `message_decoded`.
+///
+/// Records either follow each other directly (`FRAMING_NONE`), their size coming from the format (unions and
+/// conditions included), are preceded by their length (`FRAMING_LENGTH_U16`, `FRAMING_LENGTH_U32`) in the
+/// format's endianness, or are byte stuffed (`FRAMING_COBS`, `FRAMING_SLIP`). A framed record that fails to
+/// unpack is skipped, while an unframed one cannot be recovered from and clears the buffer. Frames longer
+/// than `get_max_frame_size()` (1 MiB unless changed) are dropped without being buffered, so that a bogus
+/// length cannot make the buffer grow without bounds.
+#[derive(GodotClass, Debug)]
+#[class(no_init,base=RefCounted)]
+pub struct PackDecoder {
+    pack: Gd<Pack>,
+    frames: FrameDecoder,
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl PackDecoder {
+    #[constant]
+    const FRAMING_NONE: i64 = Framing::None as i64;
+    #[constant]
+    const FRAMING_LENGTH_U16: i64 = Framing::LengthU16 as i64;
+    #[constant]
+    const FRAMING_LENGTH_U32: i64 = Framing::LengthU32 as i64;
+    #[constant]
+    const FRAMING_COBS: i64 = Framing::Cobs as i64;
+    #[constant]
+    const FRAMING_SLIP: i64 = Framing::Slip as i64;
+
+    /// Emitted for every decoded record.
+    #[signal]
+    fn message_decoded(values: VariantArray);
+
+    /// Constructs a decoder for records of `pack`, delimited according to `framing`.
+    #[func]
+    pub fn from(pack: Gd<Pack>, framing: Framing) -> Gd<Self> {
+        let endianness = *pack.bind().descriptor.endianness();
+        Gd::from_init_fn(|base| Self {
+            pack,
+            frames: FrameDecoder::new(framing.into(), endianness),
+            base,
+        })
+    }
+
+    /// Buffers `data`, returning the records it completed, possibly none.
+    #[func]
+    pub fn feed(&mut self, data: PackedByteArray) -> Array<VariantArray> {
+        self.frames.feed(data.as_slice());
+        let mut messages = Array::new();
+        {
+            let pack = self.pack.bind();
+            let descriptor = &pack.descriptor;
+            if self.frames.framing() == pack_core::Framing::None {
+                loop {
+                    let buffer = self.frames.buffered();
+                    match descriptor.try_unpack(buffer) {
+                        // Empty records would be decoded forever out of the same bytes.
+                        Ok((_, 0)) => {
+                            godot_error!(
+                                "Cannot decode empty records, discarding {} buffered bytes.",
+                                buffer.len()
+                            );
+                            self.frames.clear();
+                            break;
+                        }
+                        Ok((values, size)) => {
+                            messages.push(&values);
+                            self.frames.consume(size);
+                        }
+                        Err(UnpackError::Truncated(_)) => break,
+                        Err(UnpackError::Invalid) => {
+                            godot_error!(
+                                "Invalid record, discarding {} buffered bytes.",
+                                buffer.len()
+                            );
+                            self.frames.clear();
+                            break;
+                        }
+                    }
+                }
+            } else {
+                while let Some(frame) = self.frames.next_frame() {
+                    match frame {
+                        Ok(payload) => {
+                            if let Ok(values) = descriptor.unpack(&payload) {
+                                messages.push(&values);
+                            }
+                        }
+                        Err(error) => godot_error!("{}", error),
+                    }
+                }
+            }
+        }
+        for message in messages.iter_shared() {
+            self.base_mut()
+                .emit_signal("message_decoded", &[message.to_variant()]);
+        }
+        messages
+    }
+
+    /// Discards any buffered byte.
+    #[func]
+    pub fn reset(&mut self) {
+        self.frames.clear();
+    }
+
+    /// Sets the size of the longest frame accepted, `0` for no limit.
+    #[func]
+    pub fn set_max_frame_size(&mut self, size: i64) {
+        self.frames
+            .set_max_length((size > 0).then_some(size as usize));
+    }
+
+    /// Returns the size of the longest frame accepted, `0` for no limit.
+    #[func]
+    pub fn get_max_frame_size(&self) -> i64 {
+        self.frames.max_length().unwrap_or(0) as i64
+    }
+
+    /// Count of bytes buffered while waiting for a record to complete.
+    #[func]
+    pub fn get_buffered_bytes(&self) -> i64 {
+        self.frames.buffered().len() as i64
+    }
+}
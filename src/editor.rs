@@ -0,0 +1,214 @@
+use godot::classes::{
+    editor_plugin::DockSlot, Button, EditorPlugin, Engine, FileAccess, IEditorPlugin,
+    IVBoxContainer, Label, LineEdit, ResourceLoader, RichTextLabel, VBoxContainer,
+};
+use godot::prelude::*;
+
+use godot_pack_core::FieldSpan;
+
+use crate::pack::Pack;
+use crate::schema::PackSchema;
+
+/// Colours cycled through by consecutive fields of the hex view.
+const PALETTE: [&str; 8] = [
+    "#ff8a80", "#ffd180", "#ffff8d", "#ccff90", "#a7ffeb", "#80d8ff", "#b388ff", "#f8bbd0",
+];
+/// Colour of bytes not covered by any field, such as trailing data.
+const UNCOVERED: &str = "#808080";
+const BYTES_PER_ROW: usize = 16;
+
+/// Adds the `PackInspector` dock to the editor.
+#[derive(GodotClass, Debug)]
+#[class(tool,init,base=EditorPlugin)]
+pub struct PackInspectorPlugin {
+    dock: Option<Gd<PackInspector>>,
+    base: Base<EditorPlugin>,
+}
+
+#[godot_api]
+impl IEditorPlugin for PackInspectorPlugin {
+    fn enter_tree(&mut self) {
+        if !Engine::singleton().is_editor_hint() {
+            return;
+        }
+        let mut dock = PackInspector::new_alloc();
+        dock.set_name("Pack Inspector");
+        self.base_mut()
+            .add_control_to_dock(DockSlot::RIGHT_UL, &dock);
+        self.dock = Some(dock);
+    }
+
+    fn exit_tree(&mut self) {
+        if let Some(dock) = self.dock.take() {
+            self.base_mut().remove_control_from_docks(&dock);
+            dock.free();
+        }
+    }
+}
+
+/// Editor dock showing how a record lays out: every field of the unpacked data is coloured in a hex view and
+/// listed along with its offset, size and decoded value.
+///
+/// The layout is either a `Pack` format or a struct of a schema, written as `res://path.packschema:Struct`.
+/// The data is either a file path (`res://`, `user://` or absolute) or hexadecimal bytes, whitespace ignored.
+#[derive(GodotClass, Debug)]
+#[class(tool,init,base=VBoxContainer)]
+pub struct PackInspector {
+    layout: Option<Gd<LineEdit>>,
+    data: Option<Gd<LineEdit>>,
+    view: Option<Gd<RichTextLabel>>,
+    base: Base<VBoxContainer>,
+}
+
+#[godot_api]
+impl IVBoxContainer for PackInspector {
+    fn ready(&mut self) {
+        let mut layout = LineEdit::new_alloc();
+        layout.set_placeholder("Format, or res://file.packschema:Struct");
+        let mut data = LineEdit::new_alloc();
+        data.set_placeholder("File path, or hex bytes");
+        let mut button = Button::new_alloc();
+        button.set_text("Inspect");
+        button.connect(
+            "pressed",
+            &Callable::from_object_method(&self.to_gd(), "inspect"),
+        );
+        let mut view = RichTextLabel::new_alloc();
+        view.set_use_bbcode(true);
+        view.set_selection_enabled(true);
+        view.set_v_size_flags(godot::classes::control::SizeFlags::EXPAND_FILL);
+
+        let mut base = self.base_mut();
+        let mut label = Label::new_alloc();
+        label.set_text("Layout");
+        base.add_child(&label);
+        base.add_child(&layout);
+        let mut label = Label::new_alloc();
+        label.set_text("Data");
+        base.add_child(&label);
+        base.add_child(&data);
+        base.add_child(&button);
+        base.add_child(&view);
+        drop(base);
+
+        self.layout = Some(layout);
+        self.data = Some(data);
+        self.view = Some(view);
+    }
+}
+
+#[godot_api]
+impl PackInspector {
+    /// Unpacks the data entered in the dock and shows its fields.
+    #[func]
+    pub fn inspect(&mut self) {
+        let (Some(layout), Some(data), Some(view)) = (&self.layout, &self.data, &mut self.view)
+        else {
+            return;
+        };
+        view.clear();
+        let text = match (
+            Self::load_pack(layout.get_text()),
+            Self::load_data(data.get_text()),
+        ) {
+            (Some(pack), Some(data)) => {
+                let (spans, result) = pack.bind().descriptor.inspect(data.as_slice());
+                let mut text = Self::render(data.as_slice(), &spans);
+                if result.is_err() {
+                    text.push_str(
+                        "\n[color=#ff5252]Unpacking failed, see the output for details.[/color]",
+                    );
+                }
+                text
+            }
+            (None, _) => "[color=#ff5252]Invalid layout.[/color]".to_string(),
+            (_, None) => "[color=#ff5252]Invalid data.[/color]".to_string(),
+        };
+        view.append_text(&text);
+    }
+
+    fn load_pack(layout: GString) -> Option<Gd<Pack>> {
+        let layout = layout.to_string();
+        let layout = layout.trim();
+        if let Some((path, name)) = layout.rsplit_once(':') {
+            if path.ends_with(".packschema") {
+                let schema = ResourceLoader::singleton()
+                    .load(path)?
+                    .try_cast::<PackSchema>()
+                    .ok()?;
+                return schema.bind().get_pack(name.trim().into());
+            }
+        }
+        Pack::from(layout.into())
+    }
+
+    fn load_data(data: GString) -> Option<PackedByteArray> {
+        let data = data.to_string();
+        let data = data.trim();
+        if data.starts_with("res://") || data.starts_with("user://") || data.starts_with('/') {
+            if !FileAccess::file_exists(data) {
+                godot_error!("File `{}` does not exist.", data);
+                return None;
+            }
+            return Some(FileAccess::get_file_as_bytes(data));
+        }
+        let digits: String = data
+            .trim_start_matches("0x")
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) || !digits.len().is_multiple_of(2) {
+            godot_error!("Data is neither a file path nor hexadecimal bytes.");
+            return None;
+        }
+        let bytes: Vec<u8> = (0..digits.len())
+            .step_by(2)
+            .filter_map(|index| u8::from_str_radix(&digits[index..index + 2], 16).ok())
+            .collect();
+        Some(PackedByteArray::from(bytes.as_slice()))
+    }
+
+    /// Formats the hex view of `data` followed by the list of fields, as BBCode.
+    fn render(data: &[u8], spans: &[FieldSpan]) -> String {
+        let mut owners = vec![None; data.len()];
+        for (index, span) in spans.iter().enumerate() {
+            let end = usize::min(span.offset + span.length, data.len());
+            for owner in &mut owners[span.offset.min(end)..end] {
+                *owner = Some(index);
+            }
+        }
+        let colour =
+            |owner: Option<usize>| owner.map_or(UNCOVERED, |index| PALETTE[index % PALETTE.len()]);
+
+        let mut text = String::from("[code]");
+        for (row, bytes) in data.chunks(BYTES_PER_ROW).enumerate() {
+            let start = row * BYTES_PER_ROW;
+            text.push_str(&format!("{:08x}  ", start));
+            for (column, byte) in bytes.iter().enumerate() {
+                let owner = owners[start + column];
+                text.push_str(&format!("[color={}]{:02x}[/color] ", colour(owner), byte));
+            }
+            text.push('\n');
+        }
+        text.push('\n');
+        for (index, span) in spans.iter().enumerate() {
+            let name = span.name.as_deref().unwrap_or("-");
+            let value = if span.value.is_nil() {
+                String::new()
+            } else {
+                format!(" = {}", span.value).replace('[', "[lb]")
+            };
+            text.push_str(&format!(
+                "[color={}]{:08x} +{:<4} {:<10} {}[/color]{}\n",
+                colour(Some(index)),
+                span.offset,
+                span.length,
+                span.kind,
+                name,
+                value
+            ));
+        }
+        text.push_str("[/code]");
+        text
+    }
+}
@@ -0,0 +1,32 @@
+use godot::prelude::*;
+
+use godot_pack_core as pack_core;
+
+/// How consecutive records are delimited on a byte stream, as exposed to scripts through the
+/// `PackDecoder.FRAMING_*` constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, GodotConvert)]
+#[godot(via = i64)]
+pub enum Framing {
+    /// Records directly follow each other, their size coming from the format itself.
+    None,
+    /// Each record is preceded by its size as an unsigned 16-bit integer.
+    LengthU16,
+    /// Each record is preceded by its size as an unsigned 32-bit integer.
+    LengthU32,
+    /// Consistent Overhead Byte Stuffing, each frame ending with a zero byte.
+    Cobs,
+    /// RFC 1055 Serial Line IP, each frame being enclosed by `END` (`0xC0`) bytes.
+    Slip,
+}
+
+impl From<Framing> for pack_core::Framing {
+    fn from(framing: Framing) -> Self {
+        match framing {
+            Framing::None => pack_core::Framing::None,
+            Framing::LengthU16 => pack_core::Framing::LengthU16,
+            Framing::LengthU32 => pack_core::Framing::LengthU32,
+            Framing::Cobs => pack_core::Framing::Cobs,
+            Framing::Slip => pack_core::Framing::Slip,
+        }
+    }
+}
@@ -1,7 +1,30 @@
 use godot::prelude::*;
 
 struct PackExtension;
+mod codegen;
+pub mod decoder;
+pub mod editor;
+pub mod framing;
 pub mod pack;
+pub mod packet;
+pub mod protocol;
+pub mod registry;
+pub mod schema;
+pub mod structs;
 
 #[gdextension]
-unsafe impl ExtensionLibrary for PackExtension {}
+unsafe impl ExtensionLibrary for PackExtension {
+    fn on_level_init(level: InitLevel) {
+        if level == InitLevel::Scene {
+            registry::register_singleton();
+            schema::register_loader();
+        }
+    }
+
+    fn on_level_deinit(level: InitLevel) {
+        if level == InitLevel::Scene {
+            schema::unregister_loader();
+            registry::unregister_singleton();
+        }
+    }
+}
@@ -1,393 +1,400 @@
-use std::io::Empty;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
-use godot::classes::RefCounted;
+use godot::classes::{FileAccess, PacketPeer, RefCounted, StreamPeer};
+use godot::global::Error;
 use godot::prelude::*;
+use godot_pack_core::{
+    self as pack_core, CacheStats, Column, Endianness, FieldSpan, FormatCache, Value, ValueKind,
+    ValueSource,
+};
 
-#[derive(Debug, Clone)]
-enum FieldType {
-    String,
-    Character,
-    Bool,
-    Char,
-    UnsignedChar,
-    Short,
-    UnsignedShort,
-    Int,
-    UnsignedInt,
-    Long,
-    UnsignedLong,
-    LongLong,
-    UnsignedLongLong,
-    Float,
-    Double,
-}
-#[derive(Clone, Debug)]
-enum Endianness {
-    LittleEndian,
-    BigEndian,
+use crate::codegen;
+use crate::framing::Framing;
+use crate::registry;
+
+/// Converts a variant into a value, types other than booleans, numbers and names becoming strings.
+pub(crate) fn value_from_variant(variant: &Variant) -> Value {
+    match variant.get_type() {
+        VariantType::NIL => Value::Nil,
+        VariantType::BOOL => Value::Bool(variant.to()),
+        VariantType::INT => Value::Int(variant.to()),
+        VariantType::FLOAT => Value::Float(variant.to()),
+        VariantType::STRING_NAME => Value::Name(variant.to_string()),
+        _ => Value::String(variant.to_string()),
+    }
 }
 
-impl Endianness {
-    #[cfg(target_endian = "little")]
-    const NATIVE: Endianness = Self::LittleEndian;
+pub(crate) fn variant_from_value(value: &Value) -> Variant {
+    match value {
+        Value::Nil => Variant::nil(),
+        Value::Bool(value) => value.to_variant(),
+        Value::Int(value) => value.to_variant(),
+        Value::Float(value) => value.to_variant(),
+        Value::String(value) => GString::from(value).to_variant(),
+        Value::Name(value) => StringName::from(value).to_variant(),
+    }
+}
 
-    #[cfg(target_endian = "big")]
-    const NATIVE: Endianness = Self::BigEndian;
+/// Converts a column into the matching packed array, booleans becoming bytes.
+fn variant_from_column(column: Column) -> Variant {
+    match column {
+        Column::Bool(values) => values
+            .into_iter()
+            .map(u8::from)
+            .collect::<PackedByteArray>()
+            .to_variant(),
+        Column::Int32(values) => PackedInt32Array::from(values).to_variant(),
+        Column::Int64(values) => PackedInt64Array::from(values).to_variant(),
+        Column::Float32(values) => PackedFloat32Array::from(values).to_variant(),
+        Column::Float64(values) => PackedFloat64Array::from(values).to_variant(),
+        Column::String(values) => values
+            .iter()
+            .map(GString::from)
+            .collect::<PackedStringArray>()
+            .to_variant(),
+    }
+}
 
-    const NETWORK: Endianness = Self::BigEndian;
+/// Converts a packed array into a column, bytes becoming integers. Other variants are not columns.
+fn column_from_variant(variant: &Variant) -> Option<Column> {
+    let column = match variant.get_type() {
+        VariantType::PACKED_BYTE_ARRAY => Column::Int32(
+            variant
+                .to::<PackedByteArray>()
+                .as_slice()
+                .iter()
+                .map(|&byte| byte as i32)
+                .collect(),
+        ),
+        VariantType::PACKED_INT32_ARRAY => Column::Int32(variant.to::<PackedInt32Array>().to_vec()),
+        VariantType::PACKED_INT64_ARRAY => Column::Int64(variant.to::<PackedInt64Array>().to_vec()),
+        VariantType::PACKED_FLOAT32_ARRAY => {
+            Column::Float32(variant.to::<PackedFloat32Array>().to_vec())
+        }
+        VariantType::PACKED_FLOAT64_ARRAY => {
+            Column::Float64(variant.to::<PackedFloat64Array>().to_vec())
+        }
+        VariantType::PACKED_STRING_ARRAY => Column::String(
+            variant
+                .to::<PackedStringArray>()
+                .as_slice()
+                .iter()
+                .map(GString::to_string)
+                .collect(),
+        ),
+        _ => return None,
+    };
+    Some(column)
 }
 
-#[derive(Debug, Clone)]
-struct FieldDescriptior {
-    ty: FieldType,
-    length: usize,
-    offset: usize,
+fn span_to_dictionary(span: &FieldSpan) -> Dictionary {
+    let mut dictionary = Dictionary::new();
+    let name = span.name.as_deref().unwrap_or_default();
+    dictionary.set("name", name);
+    dictionary.set("type", span.kind);
+    dictionary.set("offset", span.offset as i64);
+    dictionary.set("size", span.length as i64);
+    dictionary.set("value", variant_from_value(&span.value));
+    dictionary
 }
 
+/// A format as seen from Godot: converts variants to and from the values of the underlying descriptor,
+/// reporting its errors.
 #[derive(Debug, Clone)]
 pub(crate) struct PackingDescriptor {
-    fields: Vec<FieldDescriptior>,
-    size: usize,
-    endianness: Endianness,
+    inner: pack_core::PackingDescriptor,
 }
 
 impl PackingDescriptor {
     pub(crate) fn sequence_from(seq: &str) -> Result<PackingDescriptor, ()> {
-        let mut order: Endianness = Endianness::NATIVE;
-        let mut fields: Vec<FieldDescriptior> = vec![];
+        match pack_core::PackingDescriptor::sequence_from(seq) {
+            Ok(inner) => Ok(PackingDescriptor { inner }),
+            Err(error) => {
+                godot_error!("{}", error);
+                Err(())
+            }
+        }
+    }
 
-        let mut running_length: usize = 0;
-        let mut offset: usize = 0;
+    /// Size of a record, the smallest one for variable layouts.
+    pub(crate) fn size(&self) -> usize {
+        self.inner.size()
+    }
 
-        let mut post_increment = |count: usize| {
-            let copy = offset.clone();
-            offset = offset + count;
-            return copy;
-        };
-        for c in seq.chars() {
-            if c.is_digit(10) {
-                running_length = running_length * 10 + c.to_digit(10).unwrap() as usize;
-            } else {
-                match c {
-                    '@' | '=' => {
-                        order = Endianness::NATIVE;
-                    }
-                    '<' => {
-                        order = Endianness::LittleEndian;
-                    }
-                    '>' => {
-                        order = Endianness::BigEndian;
-                    }
-                    '!' => {
-                        order = Endianness::NETWORK;
-                    }
-                    's' => {
-                        let length = running_length.clamp(1, u16::MAX as _) as _;
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::String,
-                            length,
-                            offset: post_increment(length),
-                        })
-                    }
-
-                    'x' => {
-                        let length = running_length.clamp(1, u16::MAX as _) as _;
-                        let _ = post_increment(length);
-                    }
-                    '?' => {
-                        let length = core::mem::size_of::<bool>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Bool,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'c' => {
-                        let length = core::mem::size_of::<u8>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Character,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'b' => {
-                        let length = core::mem::size_of::<i8>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Char,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'B' => {
-                        let length = core::mem::size_of::<u8>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::UnsignedChar,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'h' => {
-                        let length = core::mem::size_of::<i16>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Short,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'H' => {
-                        let length = core::mem::size_of::<u16>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::UnsignedShort,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'i' => {
-                        let length = core::mem::size_of::<std::ffi::c_int>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Int,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'I' => {
-                        let length = core::mem::size_of::<std::ffi::c_uint>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::UnsignedInt,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'l' => {
-                        let length = core::mem::size_of::<i32>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Long,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'L' => {
-                        let length = core::mem::size_of::<u32>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::UnsignedLong,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'q' => {
-                        let length = core::mem::size_of::<i64>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::LongLong,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'Q' => {
-                        let length = core::mem::size_of::<u64>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::UnsignedLongLong,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'f' => {
-                        let length = core::mem::size_of::<f32>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Float,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    'd' => {
-                        let length = core::mem::size_of::<f64>();
-                        fields.push(FieldDescriptior {
-                            ty: FieldType::Double,
-                            length: length,
-                            offset: post_increment(length),
-                        });
-                    }
-                    _ => {
-                        godot_error!("Invalid pattern processed.");
-                        return Err(());
-                    }
-                }
-                running_length = 0;
-            }
+    pub(crate) fn endianness(&self) -> &Endianness {
+        self.inner.endianness()
+    }
+
+    pub(crate) fn set_strict(&mut self, strict: bool) {
+        self.inner.set_strict(strict);
+    }
+
+    pub(crate) fn is_strict(&self) -> bool {
+        self.inner.is_strict()
+    }
+
+    pub(crate) fn set_enum(&mut self, name: &str, table: &Dictionary) -> Result<(), ()> {
+        let mut entries = vec![];
+        for (key, value) in table.iter_shared() {
+            let Ok(number) = value.try_to::<i64>() else {
+                godot_error!("Enum value for `{}` is not an integer.", key);
+                return Err(());
+            };
+            entries.push((key.to_string(), number));
         }
+        self.inner
+            .set_enum(name, entries)
+            .map_err(|error| godot_error!("{}", error))
+    }
 
-        Ok(PackingDescriptor {
-            fields,
-            size: offset,
-            endianness: order,
-        })
+    /// Named value fields in record order, along with their type if it is always the same.
+    pub(crate) fn properties(&self) -> Result<Vec<(String, Option<ValueKind>)>, ()> {
+        self.inner
+            .properties()
+            .map_err(|error| godot_error!("{}", error))
     }
+
     pub(crate) fn pack(&self, data: VariantArray) -> Result<PackedByteArray, ()> {
-        macro_rules! write_variant_as {
-            ($variant:expr, $slice:expr, $bounds:expr, $endianess:expr, $T:ty) => {{
-                if let Ok(value) = $variant.try_to_relaxed::<$T>() {
-                    match $endianess {
-                        Endianness::BigEndian => {
-                            $slice[$bounds].copy_from_slice(&value.to_be_bytes());
-                        }
-                        Endianness::LittleEndian => {
-                            $slice[$bounds].copy_from_slice(&value.to_le_bytes());
-                        }
-                    }
-                } else {
-                    return Err(());
-                }
-            }};
+        let values = data
+            .iter_shared()
+            .map(|variant| value_from_variant(&variant));
+        self.pack_from(values.peekable())
+    }
+
+    /// Packs the properties of `object` named after the fields, every value field needing a name.
+    pub(crate) fn pack_object(&self, object: &Gd<Object>) -> Result<PackedByteArray, ()> {
+        self.pack_from(ObjectValues::new(object))
+    }
+
+    fn pack_from(&self, values: impl ValueSource) -> Result<PackedByteArray, ()> {
+        match self.inner.pack_from(values) {
+            Ok(bytes) => Ok(PackedByteArray::from(bytes.as_slice())),
+            Err(error) => {
+                godot_error!("{}", error);
+                Err(())
+            }
         }
-        let mut output = PackedByteArray::new();
-        let endianess = self.endianness.clone();
-        output.resize(self.size);
-        output.fill(0u8);
-        {
-            let slice = output.as_mut_slice();
-            for (variant, descriptor) in data.iter_shared().zip(self.fields.iter()) {
-                let bounds = (descriptor.offset)..(descriptor.offset + descriptor.length);
-                match descriptor.ty {
-                    FieldType::String => {
-                        let string = variant.to_string();
-                        let bytes = string.as_bytes();
-                        let min_size = usize::min(bytes.len(), descriptor.length);
-                        slice[bounds][..min_size].copy_from_slice(&bytes[..min_size]);
-                    }
-                    FieldType::Character => {
-                        let string = variant.to_string().as_bytes().first().cloned();
-                        if let Some(first) = string {
-                            slice[bounds].copy_from_slice(&[first]);
-                        }
-                    }
-                    FieldType::Bool => {
-                        if let Ok(value) = variant.try_to_relaxed::<bool>() {
-                            slice[bounds].copy_from_slice(&[value as u8]);
-                        }
-                    }
-                    FieldType::Char => {
-                        write_variant_as!(variant, slice, bounds, endianess, i8);
-                    }
-                    FieldType::UnsignedChar => {
-                        write_variant_as!(variant, slice, bounds, endianess, u8);
-                    }
-                    FieldType::Short => {
-                        write_variant_as!(variant, slice, bounds, endianess, i16);
-                    }
-                    FieldType::UnsignedShort => {
-                        write_variant_as!(variant, slice, bounds, endianess, u16);
-                    }
-                    FieldType::Long | FieldType::Int => {
-                        write_variant_as!(variant, slice, bounds, endianess, i32);
-                    }
-                    FieldType::UnsignedInt | FieldType::UnsignedLong => {
-                        write_variant_as!(variant, slice, bounds, endianess, u32);
-                    }
-                    FieldType::LongLong => {
-                        write_variant_as!(variant, slice, bounds, endianess, i64);
-                    }
-                    FieldType::UnsignedLongLong => {
-                        write_variant_as!(variant, slice, bounds, endianess, u64);
-                    }
-                    FieldType::Float => {
-                        write_variant_as!(variant, slice, bounds, endianess, f32);
-                    }
-                    FieldType::Double => {
-                        write_variant_as!(variant, slice, bounds, endianess, f64);
-                    }
+    }
+
+    pub(crate) fn unpack(&self, data: &[u8]) -> Result<VariantArray, ()> {
+        match self.try_unpack(data) {
+            Ok((result, size)) => {
+                if data.len() > size {
+                    godot_warn!(
+                        "Data length ({}) is greater than expected size ({}).",
+                        data.len(),
+                        size
+                    );
                 }
+                Ok(result)
             }
+            Err(UnpackError::Truncated(size)) => {
+                report_truncated(data.len(), size);
+                Err(())
+            }
+            Err(UnpackError::Invalid) => Err(()),
         }
+    }
 
-        Ok(output)
-    }
-    pub(crate) fn unpack(&self, data: PackedByteArray) -> Result<VariantArray, ()> {
-        macro_rules! read_variant_from {
-            ($result:expr, $data:expr, $bounds:expr, $endianness:expr, $T:ty) => {{
-                let mut bytes = [0u8; core::mem::size_of::<$T>()];
-                bytes.copy_from_slice(&$data[$bounds]);
-                let extracted = match $endianness {
-                    Endianness::BigEndian => <$T>::from_be_bytes(bytes),
-                    Endianness::LittleEndian => <$T>::from_le_bytes(bytes),
-                };
-                $result.push(&extracted.to_variant());
-            }};
-        }
-        if data.len() < self.size {
-            godot_error!(
-                "Data length ({}) is less than expected size ({}).",
-                data.len(),
-                self.size
-            );
-            return Err(());
-        } else if data.len() > self.size {
-            godot_warn!(
-                "Data length ({}) is greater than expected size ({}).",
-                data.len(),
-                self.size
-            );
-        }
-        let data = data.as_slice();
-        let mut result = VariantArray::new();
-        let endianness = self.endianness.clone();
-        for field in &self.fields {
-            let bounds = (field.offset)..(field.offset + field.length);
-            match field.ty {
-                FieldType::String => {
-                    let string = str::from_utf8(&data[bounds])
-                        .map(|s| GString::from(s))
-                        .unwrap();
-                    result.push(&string.to_variant());
-                }
-                FieldType::Character => {
-                    let value = data[field.offset];
-                    let mut str = String::new();
-                    str.push(char::from(value));
-                    result.push(&str.to_variant());
-                }
-                FieldType::Bool => {
-                    let value = data[field.offset] != 0;
-                    result.push(&value.to_variant());
-                }
-                FieldType::Char => {
-                    read_variant_from!(result, data, bounds, endianness, i8);
-                }
-                FieldType::UnsignedChar => {
-                    read_variant_from!(result, data, bounds, endianness, u8);
-                }
-                FieldType::Short => {
-                    read_variant_from!(result, data, bounds, endianness, i16);
-                }
-                FieldType::UnsignedShort => {
-                    read_variant_from!(result, data, bounds, endianness, u16);
-                }
-                FieldType::Int | FieldType::Long => {
-                    read_variant_from!(result, data, bounds, endianness, i32);
-                }
-                FieldType::UnsignedInt | FieldType::UnsignedLong => {
-                    read_variant_from!(result, data, bounds, endianness, u32);
-                }
-                FieldType::LongLong => {
-                    read_variant_from!(result, data, bounds, endianness, i64);
-                }
-                FieldType::UnsignedLongLong => {
-                    read_variant_from!(result, data, bounds, endianness, u64);
-                }
-                FieldType::Float => {
-                    read_variant_from!(result, data, bounds, endianness, f32);
-                }
-                FieldType::Double => {
-                    read_variant_from!(result, data, bounds, endianness, f64);
-                    //let mut bytes = [0u8; core::mem::size_of::<f64>()];
-                    //bytes.copy_from_slice(&data[bounds]);
-                    //let extracted = match endianness {
-                    //    Endianness::BigEndian => f64::from_be_bytes(bytes),
-                    //    Endianness::LittleEndian => f64::from_le_bytes(bytes),
-                    //};
-                    //result.push(&extracted.to_variant());
-                }
+    /// Unpacks a record from the start of `data`, returning its values along with its size.
+    pub(crate) fn try_unpack(&self, data: &[u8]) -> Result<(VariantArray, usize), UnpackError> {
+        let (values, size) = self.inner.unpack(data)?;
+        let result = values.iter().map(variant_from_value).collect();
+        Ok((result, size))
+    }
+
+    /// Unpacks `data` recording where every field lies, padding and checksums included. Fields read before
+    /// an error are returned along with it.
+    pub(crate) fn inspect(&self, data: &[u8]) -> (Vec<FieldSpan>, Result<(), ()>) {
+        let (spans, result) = self.inner.inspect(data);
+        let result = result.map_err(|error| match error {
+            pack_core::Error::Truncated(size) => report_truncated(data.len(), size),
+            error => godot_error!("{}", error),
+        });
+        (spans, result)
+    }
+
+    /// Packs a batch of records from one packed array per value field, over up to `threads` threads (every
+    /// core when zero). Only the conversion of the columns touches Godot, on the calling thread.
+    pub(crate) fn pack_columns(
+        &self,
+        columns: &VariantArray,
+        threads: usize,
+    ) -> Result<PackedByteArray, ()> {
+        let mut converted = vec![];
+        for (index, variant) in columns.iter_shared().enumerate() {
+            let Some(column) = column_from_variant(&variant) else {
+                godot_error!("Column {} is not a packed array.", index);
+                return Err(());
+            };
+            converted.push(column);
+        }
+        match self.inner.pack_columns_parallel(&converted, threads) {
+            Ok(bytes) => Ok(PackedByteArray::from(bytes)),
+            Err(error) => {
+                godot_error!("{}", error);
+                Err(())
+            }
+        }
+    }
+
+    /// Unpacks a batch of back to back records into one packed array per value field, over up to `threads`
+    /// threads (every core when zero).
+    pub(crate) fn unpack_columns(&self, data: &[u8], threads: usize) -> Result<VariantArray, ()> {
+        match self.inner.unpack_columns_parallel(data, threads) {
+            Ok(columns) => Ok(columns.into_iter().map(variant_from_column).collect()),
+            Err(pack_core::Error::Truncated(size)) => {
+                report_truncated(data.len(), size);
+                Err(())
+            }
+            Err(error) => {
+                godot_error!("{}", error);
+                Err(())
+            }
+        }
+    }
+
+    /// Unpacks `data` setting the properties of `object` named after the fields, unnamed fields being ignored.
+    /// Nothing is set unless the whole record unpacks and every property exists.
+    pub(crate) fn unpack_into(&self, data: &[u8], object: &mut Gd<Object>) -> Result<(), ()> {
+        let (spans, result) = self.inspect(data);
+        result?;
+        let properties = property_names(object);
+        let mut assignments = vec![];
+        for span in spans.into_iter().filter(|span| span.has_value) {
+            let Some(name) = span.name else {
+                continue;
+            };
+            let (property, path) = property_path(&name);
+            if !properties.contains(property) {
+                godot_error!("Object has no property `{}`.", property);
+                return Err(());
+            }
+            assignments.push((path, span.value));
+        }
+        for (path, value) in assignments {
+            object.set_indexed(&path, &variant_from_value(&value));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn read_file(&self, file: &Gd<FileAccess>) -> Result<VariantArray, ()> {
+        self.read_with(&mut PackedByteArray::new(), |count| {
+            let data = file.get_buffer(count as i64);
+            if data.len() != count {
+                godot_error!(
+                    "Unexpected end of file: read {} of {} bytes.",
+                    data.len(),
+                    count
+                );
+                return Err(());
+            }
+            Ok(data)
+        })
+    }
+
+    /// Unpacks a single record asking `read` for exactly the bytes still missing, which for variable layouts
+    /// means reading the fixed part first and then whatever the discriminants and conditions require. The
+    /// record starts with the bytes already in `buffer`, which keeps those read before a failure.
+    pub(crate) fn read_with(
+        &self,
+        buffer: &mut PackedByteArray,
+        mut read: impl FnMut(usize) -> Result<PackedByteArray, ()>,
+    ) -> Result<VariantArray, ()> {
+        let mut needed = self.size();
+        loop {
+            if buffer.len() < needed {
+                buffer.extend_array(&read(needed - buffer.len())?);
+            }
+            match self.try_unpack(buffer.as_slice()) {
+                Ok((result, _)) => return Ok(result),
+                Err(UnpackError::Truncated(size)) if size > buffer.len() => needed = size,
+                Err(_) => return Err(()),
+            }
+        }
+    }
+}
+
+pub(crate) fn report_truncated(length: usize, size: usize) {
+    godot_error!(
+        "Data length ({}) is less than expected size ({}).",
+        length,
+        size
+    );
+}
+
+/// Values read from the properties of an object, by field name.
+struct ObjectValues<'o> {
+    object: &'o Gd<Object>,
+    properties: HashSet<String>,
+}
+
+impl<'o> ObjectValues<'o> {
+    fn new(object: &'o Gd<Object>) -> Self {
+        Self {
+            object,
+            properties: property_names(object),
+        }
+    }
+}
+
+impl ValueSource for ObjectValues<'_> {
+    fn next_value(&mut self, name: Option<&str>) -> Result<Option<Value>, pack_core::Error> {
+        let Some(name) = name else {
+            return Err(pack_core::Error::Pack(
+                "Cannot read an unnamed field from an object.".to_string(),
+            ));
+        };
+        let (property, path) = property_path(name);
+        if !self.properties.contains(property) {
+            return Err(pack_core::Error::Pack(format!(
+                "Object has no property `{}`.",
+                property
+            )));
+        }
+        Ok(Some(value_from_variant(&self.object.get_indexed(&path))))
+    }
+
+    fn skip_absent(&mut self, _: usize) {}
+}
+
+/// Splits a field name into the property it belongs to and the path reaching it, `position.x` standing for
+/// the `x` component of `position`.
+fn property_path(name: &str) -> (&str, NodePath) {
+    let property = name.split('.').next().unwrap_or(name);
+    (property, NodePath::from(name.replace('.', ":").as_str()))
+}
+
+/// Names of the properties of `object`, script ones included.
+fn property_names(object: &Gd<Object>) -> HashSet<String> {
+    object
+        .get_property_list()
+        .iter_shared()
+        .filter_map(|property| property.get("name"))
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// Why a record could not be unpacked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum UnpackError {
+    /// The data ends before the record does, which needs at least this many bytes.
+    Truncated(usize),
+    /// The data does not match the format, the reason has already been reported.
+    Invalid,
+}
+
+impl From<pack_core::Error> for UnpackError {
+    fn from(error: pack_core::Error) -> Self {
+        match error {
+            pack_core::Error::Truncated(size) => UnpackError::Truncated(size),
+            error => {
+                godot_error!("{}", error);
+                UnpackError::Invalid
             }
         }
-        Ok(result)
     }
 }
 
@@ -399,8 +406,8 @@ impl PackingDescriptor {
 /// | `<`       | Set little-endian                                         | –                |
 /// | `>`       | Set big-endian                                            | –                |
 /// | `!`       | Set network endianness (big-endian)                       | –                |
-/// | `...s`    | Preceded by `...` digits as length, a null terminated string          | ... or at least one byte |
-/// | `...x`       | Preceded by `...` digits as length, padding space                     | ... or at least one byte |
+/// | `...s`    | Preceded by `...` digits as length, a null terminated string          | ..., 1 without digits |
+/// | `...x`       | Preceded by `...` digits as length, padding space                     | ..., 1 without digits |
 /// | `?`       | Boolean                                                   | 1                |
 /// | `c`       | Character (byte)                                          | 1                |
 /// | `b`       | Signed 8-bit integer                                      | 1                |
@@ -415,7 +422,32 @@ impl PackingDescriptor {
 /// | `Q`       | Unsigned 64-bit integer (long long)                       | 8                |
 /// | `f`       | 32-bit floating point                                     | 4                |
 /// | `d`       | 64-bit floating point                                     | 8                |
+/// | `W`       | CRC-16/CCITT-FALSE of the preceding bytes                 | 2                |
+/// | `C`       | CRC-32 (zlib) of the preceding bytes                      | 4                |
+/// | `A`       | Adler-32 of the preceding bytes                           | 4                |
 /// | *other*   | Invalid pattern (error)                                   | –                |
+///
+/// Other codes may be preceded by a repeat count, `"3B"` standing for `"BBB"`; repeated fields cannot be named.
+///
+/// Whitespace between codes is ignored. A field may be named by following its code with `:name`,
+/// e.g. `"<B:kind H:length"`; names are terminated by whitespace or any non-identifier character.
+/// Named integer fields can be mapped to symbolic names with `set_enum`.
+///
+/// A union `{key: format, ...}` directly following an integer field picks one of several layouts from
+/// that field's value, e.g. `"<B:tag {1: ff, 2: 16s, 3: I, _: x}"`; `_` is the fallback branch and keys
+/// may be written in hexadecimal (`0x10`). Only the values of the chosen branch are packed and unpacked,
+/// right after the discriminant, so records may have different sizes.
+///
+/// A condition `?name&mask(format)` makes a group of fields present only when the preceding integer field
+/// `name` has any of the `mask` bits set (`?name(format)` tests for non-zero), e.g. `"<H:flags ?flags&0x4(f)"`.
+/// An absent group unpacks as one `null` per field, and its values may be either `null` or left out when packing.
+///
+/// `%n` aligns the next field to a multiple of `n` bytes from the start of the record, while `#n` moves to
+/// the absolute offset `n` (decimal or `0x` hexadecimal), e.g. `"<B %4 I #0x40 d"`. The count of these
+/// directives is written after them, so separate them from a following counted code: `"%4 16s"`.
+///
+/// Checksums (`W`, `C`, `A`) cover every byte of the record before them and take no value: `pack` computes
+/// them once the whole record is written, in the record's endianness, and `unpack` fails on a mismatch.
 
 #[derive(GodotClass, Debug)]
 #[class(no_init,base=RefCounted)]
@@ -423,56 +455,400 @@ pub struct Pack {
     #[var]
     pub original: GString,
 
-    pub(crate) descriptor: PackingDescriptor,
+    /// Shared with `PackRegistry` and other instances of the same format until modified.
+    pub(crate) descriptor: Arc<PackingDescriptor>,
+    /// Bytes of records only partly available yet, by stream, pulled by `read_from` before the rest.
+    pending: RefCell<HashMap<InstanceId, PackedByteArray>>,
     base: Base<RefCounted>,
 }
 
 #[godot_api]
 impl Pack {
-    /// Constructs an instance.
+    /// Constructs an instance, the format being parsed once per process and shared through `PackRegistry`.
     #[func]
     pub fn from(format: GString) -> Option<Gd<Self>> {
-        if let Ok(descriptor) = PackingDescriptor::sequence_from(&format.to_string()) {
-            Some(Gd::from_init_fn(|base| Self {
-                descriptor,
-                original: format,
-                base,
-            }))
-        } else {
-            None
-        }
+        let descriptor = registry::descriptor(&format.to_string())?;
+        Some(Gd::from_init_fn(|base| Self {
+            descriptor,
+            pending: RefCell::default(),
+            original: format,
+            base,
+        }))
     }
     /// Packs a variant array into either a `PackedByteArray` or `nil` if erroers.
     #[func]
     pub fn pack(&self, data: VariantArray) -> Variant {
         match self.descriptor.pack(data) {
             Ok(result) => Variant::from(result),
-            Err(_) => return Variant::nil(),
+            Err(_) => Variant::nil(),
         }
     }
 
     /// Unpacks a `PackedByteArray` into either a `VariantArray` or `nil` if erroers.
     #[func]
     pub fn unpack(&self, data: PackedByteArray) -> Variant {
-        match self.descriptor.unpack(data) {
+        match self.descriptor.unpack(data.as_slice()) {
+            Ok(result) => result.to_variant(),
+            Err(()) => Variant::nil(),
+        }
+    }
+
+    /// Packs a batch of records from an array holding one packed array per value field, in field order and
+    /// all of the same size, into either a `PackedByteArray` or `nil` if errors. Values are converted as by
+    /// `pack`; a `PackedByteArray` column holds numbers. The format cannot have unions or conditions.
+    #[func]
+    pub fn pack_columns(&self, columns: VariantArray) -> Variant {
+        match self.descriptor.pack_columns(&columns, 1) {
             Ok(result) => result.to_variant(),
             Err(()) => Variant::nil(),
         }
     }
+
+    /// Same as `pack_columns`, splitting large batches over up to `threads` threads, or every core when
+    /// zero. It only reads the `Pack`, so may be called from `WorkerThreadPool` tasks or `Thread`s, as long as
+    /// the `Pack` is not modified meanwhile (`set_enum`, `set_strict`).
+    #[func]
+    pub fn pack_columns_parallel(&self, columns: VariantArray, threads: i64) -> Variant {
+        match self
+            .descriptor
+            .pack_columns(&columns, threads.max(0) as usize)
+        {
+            Ok(result) => result.to_variant(),
+            Err(()) => Variant::nil(),
+        }
+    }
+
+    /// Unpacks a batch of back to back records into an array holding one packed array per value field, in
+    /// field order, or `nil` if errors: `PackedInt32Array` up to 32-bit signed integers, `PackedInt64Array`
+    /// for wider ones, `PackedFloat32Array`/`PackedFloat64Array`, `PackedStringArray` for strings and
+    /// `PackedByteArray` for booleans. The format cannot have unions or conditions.
+    #[func]
+    pub fn unpack_columns(&self, data: PackedByteArray) -> Variant {
+        match self.descriptor.unpack_columns(data.as_slice(), 1) {
+            Ok(result) => result.to_variant(),
+            Err(()) => Variant::nil(),
+        }
+    }
+
+    /// Same as `unpack_columns`, splitting large batches over up to `threads` threads, or every core when
+    /// zero, as `pack_columns_parallel` does.
+    #[func]
+    pub fn unpack_columns_parallel(&self, data: PackedByteArray, threads: i64) -> Variant {
+        match self
+            .descriptor
+            .unpack_columns(data.as_slice(), threads.max(0) as usize)
+        {
+            Ok(result) => result.to_variant(),
+            Err(()) => Variant::nil(),
+        }
+    }
+
+    /// Packs the properties of `object` named after the fields, such as `health` or the exported variables
+    /// of a script, into either a `PackedByteArray` or `nil` if errors. Every value field must be named; dots
+    /// reach into a property, `position.x` being the `x` component of `position`.
+    #[func]
+    pub fn pack_object(&self, object: Gd<Object>) -> Variant {
+        match self.descriptor.pack_object(&object) {
+            Ok(result) => result.to_variant(),
+            Err(()) => Variant::nil(),
+        }
+    }
+
+    /// Unpacks `data` into the properties of `object` named after the fields, ignoring unnamed ones. Returns
+    /// `false` if errors, in which case no property is set.
+    #[func]
+    pub fn unpack_into(&self, mut object: Gd<Object>, data: PackedByteArray) -> bool {
+        self.descriptor
+            .unpack_into(data.as_slice(), &mut object)
+            .is_ok()
+    }
+
+    /// Generates the source of a GDScript class named `class_name`, with a typed property for every field and
+    /// `to_bytes`/`from_bytes` methods converting them through this format. Fields which may be `null`, or
+    /// whose type differs between union branches, are left untyped. Enum tables are not carried over.
+    /// Returns an empty string if a value field is unnamed, or if `class_name` or a field name cannot be used
+    /// in GDScript: keywords, built-in types and members of `RefCounted` or of the generated class. The class
+    /// name cannot be that of an existing class either.
+    #[func]
+    pub fn generate_gdscript(&self, class_name: GString) -> GString {
+        let class_name = class_name.to_string();
+        if !codegen::is_class_name(&class_name) {
+            godot_error!("Invalid class name `{}`.", class_name);
+            return GString::new();
+        }
+        let Ok(properties) = self.descriptor.properties() else {
+            return GString::new();
+        };
+        for (name, _) in &properties {
+            if !codegen::is_property_name(name, &class_name) {
+                godot_error!("Field name `{}` cannot be a GDScript property.", name);
+                return GString::new();
+            }
+        }
+        codegen::gdscript_class(&class_name, &self.original.to_string(), &properties).into()
+    }
+
+    /// Unpacks `data` describing every field, padding and checksums included, as a dictionary with its `name`
+    /// (empty if unnamed), `type`, `offset`, `size` and decoded `value`. On errors, fields read so far are
+    /// still returned.
+    #[func]
+    pub fn inspect(&self, data: PackedByteArray) -> Array<Dictionary> {
+        let (spans, _) = self.descriptor.inspect(data.as_slice());
+        spans.iter().map(span_to_dictionary).collect()
+    }
+
+    /// Attaches an enum table (such as a GDScript enum, `{"NAME": value}`) to the integer field `field`.
+    /// `unpack` then returns the `StringName` of known values, while `pack` accepts either names or numbers.
+    /// Returns `false` if the field does not exist or is not an integer.
+    #[func]
+    pub fn set_enum(&mut self, field: GString, table: Dictionary) -> bool {
+        Arc::make_mut(&mut self.descriptor)
+            .set_enum(&field.to_string(), &table)
+            .is_ok()
+    }
+
+    /// When strict, values missing from an enum table are rejected by both `pack` and `unpack`.
+    #[func]
+    pub fn set_strict(&mut self, strict: bool) {
+        Arc::make_mut(&mut self.descriptor).set_strict(strict);
+    }
+
+    /// Returns whether enum tables are enforced strictly.
+    #[func]
+    pub fn is_strict(&self) -> bool {
+        self.descriptor.is_strict()
+    }
+
+    /// Packs a variant array into a single frame delimited according to `framing` (one of the
+    /// `PackDecoder.FRAMING_*` constants), returning either a `PackedByteArray` or `nil` if errors.
+    #[func]
+    pub fn pack_frame(&self, data: VariantArray, framing: Framing) -> Variant {
+        let endianness = *self.descriptor.endianness();
+        self.descriptor
+            .pack(data)
+            .and_then(|bytes| {
+                pack_core::Framing::from(framing)
+                    .encode(bytes.as_slice(), endianness)
+                    .map_err(|error| godot_error!("{}", error))
+            })
+            .map(|frame| PackedByteArray::from(frame.as_slice()).to_variant())
+            .unwrap_or_default()
+    }
+
+    /// Unpacks the first frame of `data`, delimited according to `framing`, into either a `VariantArray` or
+    /// `nil` if errors. Use a `PackDecoder` to parse frames incrementally.
+    #[func]
+    pub fn unpack_frame(&self, data: PackedByteArray, framing: Framing) -> Variant {
+        if framing == Framing::None {
+            return self.unpack(data);
+        }
+        let framing = pack_core::Framing::from(framing);
+        match framing.decode(data.as_slice(), *self.descriptor.endianness(), usize::MAX) {
+            Some((Ok(payload), _)) => self
+                .descriptor
+                .unpack(&payload)
+                .map(|result| result.to_variant())
+                .unwrap_or_default(),
+            Some((Err(error), _)) => {
+                godot_error!("{}", error);
+                Variant::nil()
+            }
+            None => {
+                godot_error!("Incomplete frame.");
+                Variant::nil()
+            }
+        }
+    }
+
+    /// Reads one record from `stream`, pulling exactly the bytes it needs, and unpacks it into either a
+    /// `VariantArray` or `nil`. It never waits for bytes: until the whole record is available the result is
+    /// `nil`, without an error and without losing anything, so it can be polled; `is_pending` then tells it
+    /// apart from a failure. Bytes of a variable layout already pulled are kept until the next call on the
+    /// same stream, and dropped on failure.
+    #[func]
+    pub fn read_from(&self, mut stream: Gd<StreamPeer>) -> Variant {
+        let id = stream.instance_id();
+        let mut buffer = self.pending.borrow_mut().remove(&id).unwrap_or_default();
+        let mut blocked = false;
+        let read = |count: usize| {
+            if stream.get_available_bytes() < count as i32 {
+                blocked = true;
+                return Err(());
+            }
+            let result = stream.get_data(count as i32);
+            let error = result.at(0).to::<Error>();
+            let data = result.at(1).to::<PackedByteArray>();
+            if error != Error::OK || data.len() != count {
+                godot_error!(
+                    "Partial read from stream: {} of {} bytes ({:?}).",
+                    data.len(),
+                    count,
+                    error
+                );
+                return Err(());
+            }
+            Ok(data)
+        };
+        match self.descriptor.read_with(&mut buffer, read) {
+            Ok(result) => result.to_variant(),
+            Err(()) => {
+                if blocked {
+                    let mut pending = self.pending.borrow_mut();
+                    pending.retain(|id, _| id.lookup_validity());
+                    pending.insert(id, buffer);
+                }
+                Variant::nil()
+            }
+        }
+    }
+
+    /// Returns whether the last `read_from` on `stream` returned `nil` because the record was not available
+    /// yet, as opposed to a failed read or an invalid record.
+    #[func]
+    pub fn is_pending(&self, stream: Gd<StreamPeer>) -> bool {
+        self.pending.borrow().contains_key(&stream.instance_id())
+    }
+
+    /// Packs `data` and writes it to `stream`, returning the error of either step.
+    #[func]
+    pub fn write_to(&self, mut stream: Gd<StreamPeer>, data: VariantArray) -> Error {
+        match self.descriptor.pack(data) {
+            Ok(bytes) => stream.put_data(&bytes),
+            Err(()) => Error::ERR_INVALID_DATA,
+        }
+    }
+
+    /// Packs `data` and sends it as a single packet through `peer`, returning the error of either step.
+    #[func]
+    pub fn send(&self, mut peer: Gd<PacketPeer>, data: VariantArray) -> Error {
+        match self.descriptor.pack(data) {
+            Ok(bytes) => peer.put_packet(&bytes),
+            Err(()) => Error::ERR_INVALID_DATA,
+        }
+    }
+
+    /// Receives the next packet of `peer` and unpacks it into either a `VariantArray` or `nil`, if errors or
+    /// if no packet is available.
+    #[func]
+    pub fn receive(&self, mut peer: Gd<PacketPeer>) -> Variant {
+        if peer.get_available_packet_count() == 0 {
+            return Variant::nil();
+        }
+        let packet = peer.get_packet();
+        if peer.get_packet_error() != Error::OK {
+            godot_error!("Failed to receive packet: {:?}.", peer.get_packet_error());
+            return Variant::nil();
+        }
+        self.unpack(packet)
+    }
+
+    /// Reads one record at the current position of `file`, unpacking it into either a `VariantArray` or `nil`
+    /// if errors, such as the file ending before the record does.
+    #[func]
+    pub fn read_file(&self, file: Gd<FileAccess>) -> Variant {
+        match self.descriptor.read_file(&file) {
+            Ok(result) => result.to_variant(),
+            Err(()) => Variant::nil(),
+        }
+    }
+
+    /// Packs `data` and writes it at the current position of `file`, returning the error of either step.
+    #[func]
+    pub fn write_file(&self, mut file: Gd<FileAccess>, data: VariantArray) -> Error {
+        match self.descriptor.pack(data) {
+            Ok(bytes) if file.store_buffer(&bytes) => Error::OK,
+            Ok(_) => file.get_error(),
+            Err(()) => Error::ERR_INVALID_DATA,
+        }
+    }
+
+    /// Iterates over the records of `file`, from its current position to its end, reading one at a time:
+    /// `for record in pack.iter_file(file): ...`.
+    #[func]
+    pub fn iter_file(&self, file: Gd<FileAccess>) -> Gd<PackFileIterator> {
+        Gd::from_init_fn(|base| PackFileIterator {
+            pack: self.to_gd(),
+            file,
+            current: Variant::nil(),
+            base,
+        })
+    }
+}
+
+/// Iterator over the records of a file, as returned by `Pack.iter_file`.
+#[derive(GodotClass, Debug)]
+#[class(no_init,base=RefCounted)]
+pub struct PackFileIterator {
+    pack: Gd<Pack>,
+    file: Gd<FileAccess>,
+    current: Variant,
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl PackFileIterator {
+    fn advance(&mut self) -> bool {
+        let position = self.file.get_position();
+        if position >= self.file.get_length() {
+            return false;
+        }
+        match self.pack.bind().descriptor.read_file(&self.file) {
+            // Empty records would never reach the end of the file.
+            Ok(_) if self.file.get_position() == position => {
+                godot_error!("Cannot iterate over empty records.");
+                false
+            }
+            Ok(result) => {
+                self.current = result.to_variant();
+                true
+            }
+            Err(()) => false,
+        }
+    }
+
+    #[func]
+    fn _iter_init(&mut self, _iter: VariantArray) -> bool {
+        self.advance()
+    }
+
+    #[func]
+    fn _iter_next(&mut self, _iter: VariantArray) -> bool {
+        self.advance()
+    }
+
+    #[func]
+    fn _iter_get(&self, _iter: Variant) -> Variant {
+        self.current.clone()
+    }
+}
+
+/// Descriptors used so far, keyed by their format.
+pub(crate) type DescriptorCache = FormatCache<Arc<PackingDescriptor>>;
+
+/// Converts the statistics of a cache into `{"hits": int, "misses": int, "evictions": int, "size": int}`.
+pub(crate) fn stats_dictionary(stats: CacheStats) -> Dictionary {
+    let mut dictionary = Dictionary::new();
+    dictionary.set("hits", stats.hits as i64);
+    dictionary.set("misses", stats.misses as i64);
+    dictionary.set("evictions", stats.evictions as i64);
+    dictionary.set("size", stats.size as i64);
+    dictionary
 }
 
 /// A cached version of `Pack` that allows to pack and unpack multiple formats using only one object.
+/// It keeps up to `get_capacity()` formats (256 unless changed), evicting the least recently used one beyond,
+/// and takes missing ones from `PackRegistry`.
 #[derive(GodotClass, Debug)]
 #[class(base=RefCounted)]
 pub struct CachedPack {
-    cache: std::collections::HashMap<String, PackingDescriptor>,
+    cache: DescriptorCache,
     base: Base<RefCounted>,
 }
 #[godot_api]
 impl IRefCounted for CachedPack {
     fn init(base: Base<RefCounted>) -> Self {
         Self {
-            cache: Default::default(),
+            cache: DescriptorCache::with_capacity(Some(Self::DEFAULT_CAPACITY)),
             base,
         }
     }
@@ -480,26 +856,51 @@ impl IRefCounted for CachedPack {
 
 #[godot_api]
 impl CachedPack {
-    fn get_or_create_descriptor(&mut self, format: GString) -> Option<&PackingDescriptor> {
-        let key = format.to_string();
+    const DEFAULT_CAPACITY: usize = 256;
 
-        if self.cache.contains_key(&key) {
-            return self.cache.get(&key);
-        }
-        if let Ok(descriptor) = PackingDescriptor::sequence_from(&key) {
-            self.cache.insert(key.clone(), descriptor);
-            return self.cache.get(&key);
-        } else {
-            None
-        }
+    fn get_or_create_descriptor(&mut self, format: GString) -> Option<&PackingDescriptor> {
+        self.cache
+            .get_or_insert_with(&format.to_string(), registry::descriptor)
+            .map(Arc::as_ref)
     }
 
     /// Flush internal cache.
     #[func]
-    fn flush(&mut self) -> () {
+    fn flush(&mut self) {
         self.cache.clear();
     }
 
+    /// Sets how many formats are kept at most, `0` for no limit, evicting the least recently used ones
+    /// already beyond it.
+    #[func]
+    fn set_capacity(&mut self, capacity: i64) {
+        self.cache
+            .set_capacity((capacity > 0).then_some(capacity as usize));
+    }
+
+    /// Returns how many formats are kept at most, `0` for no limit.
+    #[func]
+    fn get_capacity(&self) -> i64 {
+        self.cache.capacity().unwrap_or(0) as i64
+    }
+
+    /// Returns `{"hits": int, "misses": int, "evictions": int, "size": int}`: formats found in the cache,
+    /// formats taken from `PackRegistry` (invalid ones included), formats evicted, and formats currently cached.
+    #[func]
+    fn get_stats(&self) -> Dictionary {
+        stats_dictionary(self.cache.stats())
+    }
+
+    /// Parses `formats` ahead of time, such as when loading, returning `false` if any is invalid.
+    #[func]
+    fn precompile(&mut self, formats: PackedStringArray) -> bool {
+        let mut valid = true;
+        for format in formats.as_slice() {
+            valid &= self.get_or_create_descriptor(format.clone()).is_some();
+        }
+        valid
+    }
+
     /// Pack data using provided `format'.
     #[func]
     pub(crate) fn pack(&mut self, format: GString, data: VariantArray) -> Variant {
@@ -517,7 +918,7 @@ impl CachedPack {
     pub(crate) fn unpack(&mut self, format: GString, data: PackedByteArray) -> Variant {
         match self.get_or_create_descriptor(format) {
             Some(descriptor) => descriptor
-                .unpack(data)
+                .unpack(data.as_slice())
                 .map(|v| v.to_variant())
                 .unwrap_or(Variant::nil()),
             None => Variant::nil(),
@@ -0,0 +1,105 @@
+use godot::classes::{PacketPeer, RefCounted};
+use godot::global::Error;
+use godot::prelude::*;
+
+use crate::pack::Pack;
+use crate::protocol::{receive_packet, PackProtocol};
+
+/// Routes packets to the `Pack` registered for their message ID, a custom protocol being a header carrying
+/// the ID followed by a payload whose layout depends on it.
+///
+/// The header is itself a `Pack` whose first value is the ID, e.g. `Pack.from("<H")`; every received
+/// packet is unpacked with the `Pack` registered for its ID, then handed to its handler (if any) and emitted
+/// through `message_received`. A router is a `PackProtocol` whose messages are only known by their ID, the
+/// name of each one being its ID as a string; `get_protocol` returns it.
+#[derive(GodotClass, Debug)]
+#[class(no_init,base=RefCounted)]
+pub struct PackRouter {
+    protocol: Gd<PackProtocol>,
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl PackRouter {
+    /// Emitted for every routed message, with the values unpacked by its `Pack`.
+    #[signal]
+    fn message_received(id: i64, values: VariantArray);
+
+    /// Constructs a router whose packets start with `header`.
+    #[func]
+    pub fn from(header: Gd<Pack>) -> Gd<Self> {
+        Gd::from_init_fn(|base| Self {
+            protocol: PackProtocol::with_header(header),
+            base,
+        })
+    }
+
+    /// Registers the payload layout of message `id`, along with an optional `handler` called with its values,
+    /// replacing any previous registration of `id`.
+    #[func]
+    pub fn register(&mut self, id: i64, pack: Gd<Pack>, handler: Callable) {
+        let mut protocol = self.protocol.bind_mut();
+        protocol.remove(id);
+        protocol.insert(id, StringName::from(id.to_string().as_str()), pack, handler);
+    }
+
+    /// Removes message `id`, returning whether it was registered.
+    #[func]
+    pub fn unregister(&mut self, id: i64) -> bool {
+        self.protocol.bind_mut().remove(id)
+    }
+
+    /// Encodes message `id` into a packet, returning either a `PackedByteArray` or `nil` if errors.
+    #[func]
+    pub fn encode(&self, id: i64, values: VariantArray) -> Variant {
+        self.protocol
+            .bind()
+            .encode_message(id, values)
+            .map(|packet| packet.to_variant())
+            .unwrap_or_default()
+    }
+
+    /// Encodes message `id` and sends it through `peer`, returning the error of either step.
+    #[func]
+    pub fn send(&self, mut peer: Gd<PacketPeer>, id: i64, values: VariantArray) -> Error {
+        match self.protocol.bind().encode_message(id, values) {
+            Ok(packet) => peer.put_packet(&packet),
+            Err(()) => Error::ERR_INVALID_DATA,
+        }
+    }
+
+    /// Routes a received packet, returning whether it was recognized and unpacked. Handlers may register and
+    /// unregister messages, their own included.
+    #[func]
+    pub fn route(&mut self, packet: PackedByteArray) -> bool {
+        let mut protocol = self.protocol.clone();
+        // Holding the base lets the handler and signal receivers call back into the router.
+        let mut base = self.base_mut();
+        let Some((id, values)) = protocol.bind_mut().dispatch(packet.as_slice()) else {
+            return false;
+        };
+        base.emit_signal("message_received", &[id.to_variant(), values.to_variant()]);
+        true
+    }
+
+    /// Routes every packet available on `peer`, returning how many of them were routed.
+    #[func]
+    pub fn poll(&mut self, mut peer: Gd<PacketPeer>) -> i64 {
+        let mut routed = 0;
+        while peer.get_available_packet_count() > 0 {
+            let Some(packet) = receive_packet(&mut peer) else {
+                continue;
+            };
+            if self.route(packet) {
+                routed += 1;
+            }
+        }
+        routed
+    }
+
+    /// The protocol holding the registered messages.
+    #[func]
+    pub fn get_protocol(&self) -> Gd<PackProtocol> {
+        self.protocol.clone()
+    }
+}
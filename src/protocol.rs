@@ -0,0 +1,309 @@
+use std::collections::HashMap;
+
+use godot::classes::{PacketPeer, RefCounted};
+use godot::global::Error;
+use godot::prelude::*;
+
+use crate::pack::{Pack, PackingDescriptor, UnpackError};
+
+/// Packs message `id` behind `header`, its payload being `values` packed by `payload`.
+fn encode_message(
+    header: &PackingDescriptor,
+    id: i64,
+    payload: &PackingDescriptor,
+    values: VariantArray,
+) -> Result<PackedByteArray, ()> {
+    let mut bytes = header.pack(varray![id])?;
+    bytes.extend_array(&payload.pack(values)?);
+    Ok(bytes)
+}
+
+/// Reads the message ID at the start of `data`, returning it along with the size of `header`.
+fn decode_header(header: &PackingDescriptor, data: &[u8]) -> Result<(i64, usize), ()> {
+    let (values, size) = match header.try_unpack(data) {
+        Ok(result) => result,
+        Err(UnpackError::Truncated(size)) => {
+            godot_error!(
+                "Message of {} bytes is shorter than its header ({} bytes).",
+                data.len(),
+                size
+            );
+            return Err(());
+        }
+        Err(UnpackError::Invalid) => return Err(()),
+    };
+    let Some(id) = values.get(0).and_then(|id| id.try_to::<i64>().ok()) else {
+        godot_error!("Message header does not start with an integer ID.");
+        return Err(());
+    };
+    Ok((id, size))
+}
+
+/// A registered message: its name, the `Pack` of its payload and the handler of received ones.
+#[derive(Debug)]
+struct Message {
+    name: StringName,
+    pack: Gd<Pack>,
+    handler: Callable,
+}
+
+/// A family of messages, each one registered as `(id, name, format)`, sharing an ID header.
+///
+/// `encode(name, values)` prefixes the packed values with the message ID, `decode(bytes)` reads the ID back
+/// and returns `{"id": id, "name": name, "values": values}`. Formats are parsed once and shared through the
+/// `PackRegistry`. Received packets can also be routed: `route` and `poll` unpack them, call the handler of
+/// their message, if any, and emit `message_received`.
+#[derive(GodotClass, Debug)]
+#[class(no_init,base=RefCounted)]
+pub struct PackProtocol {
+    header: Gd<Pack>,
+    messages: HashMap<i64, Message>,
+    ids: HashMap<StringName, i64>,
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl PackProtocol {
+    /// Emitted for every routed message, with the values unpacked by its format.
+    #[signal]
+    fn message_received(id: i64, name: StringName, values: VariantArray);
+
+    /// Constructs a protocol whose messages start with `header`, a format whose first value is the message
+    /// ID (e.g. `"<H"`), or `nil` if the format is invalid.
+    #[func]
+    pub fn from(header: GString) -> Option<Gd<Self>> {
+        Pack::from(header).map(Self::with_header)
+    }
+
+    /// Registers message `name` with ID `id` and payload `format`, returning `false` if the format is invalid
+    /// or either the ID or the name is already taken.
+    #[func]
+    pub fn register(&mut self, id: i64, name: StringName, format: GString) -> bool {
+        match Pack::from(format) {
+            Some(pack) => self.insert(id, name, pack, Callable::invalid()),
+            None => false,
+        }
+    }
+
+    /// Registers message `name` with ID `id`, its payload being unpacked by `pack` and handed to `handler`
+    /// when routed. Returns `false` if either the ID or the name is already taken.
+    #[func]
+    pub fn register_pack(
+        &mut self,
+        id: i64,
+        name: StringName,
+        pack: Gd<Pack>,
+        handler: Callable,
+    ) -> bool {
+        self.insert(id, name, pack, handler)
+    }
+
+    /// Sets the handler called with the values of every routed message `name`, returning whether it is
+    /// registered.
+    #[func]
+    pub fn set_handler(&mut self, name: StringName, handler: Callable) -> bool {
+        let Some(id) = self.ids.get(&name) else {
+            godot_error!("No message named {}.", name);
+            return false;
+        };
+        if let Some(message) = self.messages.get_mut(id) {
+            message.handler = handler;
+        }
+        true
+    }
+
+    /// Removes message `name`, returning whether it was registered.
+    #[func]
+    pub fn unregister(&mut self, name: StringName) -> bool {
+        match self.ids.get(&name) {
+            Some(&id) => self.remove(id),
+            None => false,
+        }
+    }
+
+    /// Whether a message called `name` is registered.
+    #[func]
+    pub fn has_message(&self, name: StringName) -> bool {
+        self.ids.contains_key(&name)
+    }
+
+    /// Names of the registered messages.
+    #[func]
+    pub fn get_message_names(&self) -> Array<StringName> {
+        self.ids.keys().cloned().collect()
+    }
+
+    /// Packs message `name`, prefixed by its ID, into either a `PackedByteArray` or `nil` if errors.
+    #[func]
+    pub fn encode(&self, name: StringName, values: VariantArray) -> Variant {
+        self.encode_named(&name, values)
+            .map(|bytes| bytes.to_variant())
+            .unwrap_or_default()
+    }
+
+    /// Unpacks any registered message into `{"id": id, "name": name, "values": values}`, or `nil` if errors.
+    #[func]
+    pub fn decode(&self, data: PackedByteArray) -> Variant {
+        self.decode_message(data.as_slice())
+            .map(|(id, message, values)| {
+                vdict! {
+                    "id": id,
+                    "name": message.name.clone(),
+                    "values": values,
+                }
+                .to_variant()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Encodes message `name` and sends it as a single packet through `peer`, returning the error of either
+    /// step.
+    #[func]
+    pub fn send(&self, mut peer: Gd<PacketPeer>, name: StringName, values: VariantArray) -> Error {
+        match self.encode_named(&name, values) {
+            Ok(bytes) => peer.put_packet(&bytes),
+            Err(()) => Error::ERR_INVALID_DATA,
+        }
+    }
+
+    /// Receives the next packet of `peer` and decodes it like `decode`, returning `nil` if errors or if no
+    /// packet is available.
+    #[func]
+    pub fn receive(&self, mut peer: Gd<PacketPeer>) -> Variant {
+        match receive_packet(&mut peer) {
+            Some(packet) => self.decode(packet),
+            None => Variant::nil(),
+        }
+    }
+
+    /// Routes a received packet, returning whether it was recognized and unpacked. Handlers may register and
+    /// unregister messages, their own included.
+    #[func]
+    pub fn route(&mut self, packet: PackedByteArray) -> bool {
+        self.dispatch(packet.as_slice()).is_some()
+    }
+
+    /// Routes every packet available on `peer`, returning how many of them were routed.
+    #[func]
+    pub fn poll(&mut self, mut peer: Gd<PacketPeer>) -> i64 {
+        let mut routed = 0;
+        while peer.get_available_packet_count() > 0 {
+            let Some(packet) = receive_packet(&mut peer) else {
+                continue;
+            };
+            if self.route(packet) {
+                routed += 1;
+            }
+        }
+        routed
+    }
+}
+
+impl PackProtocol {
+    /// Constructs a protocol whose messages start with `header`.
+    pub(crate) fn with_header(header: Gd<Pack>) -> Gd<Self> {
+        Gd::from_init_fn(|base| Self {
+            header,
+            messages: HashMap::new(),
+            ids: HashMap::new(),
+            base,
+        })
+    }
+
+    pub(crate) fn insert(
+        &mut self,
+        id: i64,
+        name: StringName,
+        pack: Gd<Pack>,
+        handler: Callable,
+    ) -> bool {
+        if self.messages.contains_key(&id) || self.ids.contains_key(&name) {
+            godot_error!("Message {} ({}) is already registered.", name, id);
+            return false;
+        }
+        self.ids.insert(name.clone(), id);
+        self.messages.insert(
+            id,
+            Message {
+                name,
+                pack,
+                handler,
+            },
+        );
+        true
+    }
+
+    /// Removes message `id`, returning whether it was registered.
+    pub(crate) fn remove(&mut self, id: i64) -> bool {
+        let Some(message) = self.messages.remove(&id) else {
+            return false;
+        };
+        self.ids.remove(&message.name);
+        true
+    }
+
+    fn encode_named(&self, name: &StringName, values: VariantArray) -> Result<PackedByteArray, ()> {
+        let Some(&id) = self.ids.get(name) else {
+            godot_error!("No message named {}.", name);
+            return Err(());
+        };
+        self.encode_message(id, values)
+    }
+
+    pub(crate) fn encode_message(
+        &self,
+        id: i64,
+        values: VariantArray,
+    ) -> Result<PackedByteArray, ()> {
+        let Some(message) = self.messages.get(&id) else {
+            godot_error!("No message registered with ID {}.", id);
+            return Err(());
+        };
+        encode_message(
+            &self.header.bind().descriptor,
+            id,
+            &message.pack.bind().descriptor,
+            values,
+        )
+    }
+
+    fn decode_message(&self, data: &[u8]) -> Result<(i64, &Message, VariantArray), ()> {
+        let (id, size) = decode_header(&self.header.bind().descriptor, data)?;
+        let Some(message) = self.messages.get(&id) else {
+            godot_error!("No message registered with ID {}.", id);
+            return Err(());
+        };
+        let values = message.pack.bind().descriptor.unpack(&data[size..])?;
+        Ok((id, message, values))
+    }
+
+    /// Unpacks a packet, hands its values to the handler of its message and emits `message_received`,
+    /// returning the ID and values of the message.
+    pub(crate) fn dispatch(&mut self, packet: &[u8]) -> Option<(i64, VariantArray)> {
+        let (id, message, values) = self.decode_message(packet).ok()?;
+        let (name, handler) = (message.name.clone(), message.handler.clone());
+        // Holding the base lets the handler and signal receivers call back into the protocol.
+        let mut base = self.base_mut();
+        if handler.is_valid() {
+            handler.callv(&varray![values]);
+        }
+        base.emit_signal(
+            "message_received",
+            &[id.to_variant(), name.to_variant(), values.to_variant()],
+        );
+        Some((id, values))
+    }
+}
+
+/// Receives the next packet of `peer`, if any.
+pub(crate) fn receive_packet(peer: &mut Gd<PacketPeer>) -> Option<PackedByteArray> {
+    if peer.get_available_packet_count() == 0 {
+        return None;
+    }
+    let packet = peer.get_packet();
+    if peer.get_packet_error() != Error::OK {
+        godot_error!("Failed to receive packet: {:?}.", peer.get_packet_error());
+        return None;
+    }
+    Some(packet)
+}
@@ -0,0 +1,94 @@
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard, PoisonError};
+
+use godot::classes::Engine;
+use godot::prelude::*;
+
+use crate::pack::{stats_dictionary, DescriptorCache, PackingDescriptor};
+
+/// Descriptors shared by the whole process, whichever thread or script asks for them.
+static DESCRIPTORS: LazyLock<Mutex<DescriptorCache>> = LazyLock::new(|| {
+    Mutex::new(DescriptorCache::with_capacity(Some(
+        PackRegistry::DEFAULT_CAPACITY,
+    )))
+});
+
+fn descriptors() -> MutexGuard<'static, DescriptorCache> {
+    // The cache stays consistent even if a panic happened while it was locked.
+    DESCRIPTORS.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Returns the shared descriptor of `format`, parsing it on first use.
+pub(crate) fn descriptor(format: &str) -> Option<Arc<PackingDescriptor>> {
+    descriptors().get_or_insert_with(format, parse).cloned()
+}
+
+fn parse(format: &str) -> Option<Arc<PackingDescriptor>> {
+    PackingDescriptor::sequence_from(format).ok().map(Arc::new)
+}
+
+/// Process-wide cache of parsed formats, available as the `PackRegistry` singleton. `Pack.from` and
+/// `CachedPack` take their descriptors from it, so that each format is parsed once whichever script or
+/// thread asks for it. It keeps up to `get_capacity()` formats (1024 unless changed), evicting the least
+/// recently used one beyond; instances already using an evicted format keep working.
+#[derive(GodotClass)]
+#[class(init, base=Object)]
+pub struct PackRegistry {
+    base: Base<Object>,
+}
+
+#[godot_api]
+impl PackRegistry {
+    const DEFAULT_CAPACITY: usize = 1024;
+
+    /// Parses `formats` ahead of time, such as when loading, returning `false` if any is invalid.
+    #[func]
+    fn precompile(&self, formats: PackedStringArray) -> bool {
+        let mut descriptors = descriptors();
+        let mut valid = true;
+        for format in formats.as_slice() {
+            valid &= descriptors
+                .get_or_insert_with(&format.to_string(), parse)
+                .is_some();
+        }
+        valid
+    }
+
+    /// Forgets every format, which is parsed again when next used.
+    #[func]
+    fn flush(&self) {
+        descriptors().clear();
+    }
+
+    /// Sets how many formats are kept at most, `0` for no limit, evicting the least recently used ones
+    /// already beyond it.
+    #[func]
+    fn set_capacity(&self, capacity: i64) {
+        descriptors().set_capacity((capacity > 0).then_some(capacity as usize));
+    }
+
+    /// Returns how many formats are kept at most, `0` for no limit.
+    #[func]
+    fn get_capacity(&self) -> i64 {
+        descriptors().capacity().unwrap_or(0) as i64
+    }
+
+    /// Returns `{"hits": int, "misses": int, "evictions": int, "size": int}`: formats found in the registry,
+    /// formats parsed (invalid ones included), formats evicted, and formats currently registered.
+    #[func]
+    fn get_stats(&self) -> Dictionary {
+        stats_dictionary(descriptors().stats())
+    }
+}
+
+pub(crate) fn register_singleton() {
+    let registry = PackRegistry::new_alloc();
+    Engine::singleton().register_singleton("PackRegistry", &registry);
+}
+
+pub(crate) fn unregister_singleton() {
+    let mut engine = Engine::singleton();
+    if let Some(registry) = engine.get_singleton("PackRegistry") {
+        engine.unregister_singleton("PackRegistry");
+        registry.free();
+    }
+}
@@ -0,0 +1,172 @@
+use std::cell::RefCell;
+
+use godot::classes::{
+    FileAccess, IResourceFormatLoader, Resource, ResourceFormatLoader, ResourceLoader,
+};
+use godot::global::Error;
+use godot::prelude::*;
+
+use crate::pack::{Pack, PackingDescriptor};
+
+/// A set of named struct definitions, each one a `Pack` format, shared as a resource between scripts.
+///
+/// Schemas are usually loaded from `.packschema` text files, holding one `Name = format` definition per line,
+/// blank lines and lines starting with `#` being ignored:
+/// ```text
+/// # Player state, sent every tick.
+/// Player = <H:id f:x f:y f:z
+/// Chat = <H:id 64s
+/// ```
+#[derive(GodotClass, Debug)]
+#[class(tool,init,base=Resource)]
+pub struct PackSchema {
+    /// Struct definitions, mapping each name to its format.
+    #[export]
+    definitions: Dictionary,
+    base: Base<Resource>,
+}
+
+#[godot_api]
+impl PackSchema {
+    /// Parses the text of a `.packschema` file, returning `nil` if any line or format is invalid.
+    #[func]
+    pub fn parse(source: GString) -> Option<Gd<Self>> {
+        let mut definitions = Dictionary::new();
+        for (number, line) in source.to_string().lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((name, format)) = line.split_once('=') else {
+                godot_error!("Line {}: expected `Name = format`.", number + 1);
+                return None;
+            };
+            let (name, format) = (name.trim(), format.trim());
+            if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+                godot_error!("Line {}: invalid struct name `{}`.", number + 1, name);
+                return None;
+            }
+            if definitions.contains_key(name) {
+                godot_error!("Line {}: struct `{}` is already defined.", number + 1, name);
+                return None;
+            }
+            if PackingDescriptor::sequence_from(format).is_err() {
+                godot_error!("Line {}: invalid format for struct `{}`.", number + 1, name);
+                return None;
+            }
+            definitions.set(name, format);
+        }
+        Some(Gd::from_init_fn(|base| Self { definitions, base }))
+    }
+
+    /// Whether a struct called `name` is defined.
+    #[func]
+    pub fn has_struct(&self, name: GString) -> bool {
+        self.definitions.contains_key(name)
+    }
+
+    /// Names of the defined structs. Keys which are not strings, as may be set from the inspector, are
+    /// reported and skipped.
+    #[func]
+    pub fn get_struct_names(&self) -> PackedStringArray {
+        self.definitions
+            .keys_array()
+            .iter_shared()
+            .filter_map(|name| match name.try_to::<GString>() {
+                Ok(name) => Some(name),
+                Err(_) => {
+                    godot_error!("Struct name `{}` is not a string.", name);
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Format of struct `name`, empty if not defined or if it is not a string.
+    #[func]
+    pub fn get_format(&self, name: GString) -> GString {
+        let Some(format) = self.definitions.get(name.clone()) else {
+            return GString::new();
+        };
+        format.try_to::<GString>().unwrap_or_else(|_| {
+            godot_error!("Format of struct `{}` is not a string: `{}`.", name, format);
+            GString::new()
+        })
+    }
+
+    /// Constructs the `Pack` of struct `name`, or `nil` if not defined or invalid.
+    #[func]
+    pub fn get_pack(&self, name: GString) -> Option<Gd<Pack>> {
+        if !self.has_struct(name.clone()) {
+            godot_error!("No struct named `{}`.", name);
+            return None;
+        }
+        let format = self.get_format(name);
+        if format.is_empty() {
+            return None;
+        }
+        Pack::from(format)
+    }
+}
+
+/// Loads `.packschema` files as `PackSchema` resources.
+#[derive(GodotClass, Debug)]
+#[class(tool,init,base=ResourceFormatLoader)]
+pub struct PackSchemaLoader {
+    base: Base<ResourceFormatLoader>,
+}
+
+#[godot_api]
+impl IResourceFormatLoader for PackSchemaLoader {
+    fn get_recognized_extensions(&self) -> PackedStringArray {
+        PackedStringArray::from(&[GString::from("packschema")])
+    }
+
+    fn handles_type(&self, type_: StringName) -> bool {
+        type_ == StringName::from("PackSchema") || type_ == StringName::from("Resource")
+    }
+
+    fn get_resource_type(&self, path: GString) -> GString {
+        if path.get_extension().to_lower() == GString::from("packschema") {
+            GString::from("PackSchema")
+        } else {
+            GString::new()
+        }
+    }
+
+    fn load(
+        &self,
+        path: GString,
+        _original_path: GString,
+        _use_sub_threads: bool,
+        _cache_mode: i32,
+    ) -> Variant {
+        if !FileAccess::file_exists(&path) {
+            return Error::ERR_FILE_NOT_FOUND.to_variant();
+        }
+        match PackSchema::parse(FileAccess::get_file_as_string(&path)) {
+            Some(schema) => schema.to_variant(),
+            None => {
+                godot_error!("Failed to parse schema `{}`.", path);
+                Error::ERR_PARSE_ERROR.to_variant()
+            }
+        }
+    }
+}
+
+thread_local! {
+    static LOADER: RefCell<Option<Gd<PackSchemaLoader>>> = const { RefCell::new(None) };
+}
+
+/// Makes `.packschema` files loadable through `load()` and `preload()`.
+pub(crate) fn register_loader() {
+    let loader = PackSchemaLoader::new_gd();
+    ResourceLoader::singleton().add_resource_format_loader(&loader);
+    LOADER.with_borrow_mut(|slot| *slot = Some(loader));
+}
+
+pub(crate) fn unregister_loader() {
+    if let Some(loader) = LOADER.with_borrow_mut(Option::take) {
+        ResourceLoader::singleton().remove_resource_format_loader(&loader);
+    }
+}
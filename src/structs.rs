@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use godot::prelude::*;
+
+use crate::pack::{report_truncated, PackingDescriptor, UnpackError};
+use crate::registry;
+
+fn descriptor(format: &GString) -> Option<Arc<PackingDescriptor>> {
+    registry::descriptor(&format.to_string())
+}
+
+/// Resolves an offset into `length` bytes, negative ones counting from the end as in Python.
+fn resolve_offset(offset: i64, length: usize) -> Option<usize> {
+    let resolved = if offset < 0 {
+        length as i64 + offset
+    } else {
+        offset
+    };
+    if (0..=length as i64).contains(&resolved) {
+        Some(resolved as usize)
+    } else {
+        godot_error!("Offset {} is out of a buffer of {} bytes.", offset, length);
+        None
+    }
+}
+
+/// Unpacks a record from `data` at `offset`, returning its values along with its size.
+fn unpack_at(
+    descriptor: &PackingDescriptor,
+    data: &[u8],
+    offset: usize,
+) -> Option<(VariantArray, usize)> {
+    let data = &data[offset..];
+    match descriptor.try_unpack(data) {
+        Ok(result) => Some(result),
+        Err(UnpackError::Truncated(size)) => {
+            report_truncated(data.len(), size);
+            None
+        }
+        Err(UnpackError::Invalid) => None,
+    }
+}
+
+/// Static functions mirroring python's struct module, for one-off use without a `Pack` instance:
+/// `Struct.pack("<HH", [1, 2])`, `Struct.unpack("<HH", bytes)`, `Struct.calcsize("<HH")` and so on.
+/// Formats are taken from the `PackRegistry`, so each one is parsed once. Errors are reported and return
+/// `null`, or `-1` for `calcsize`.
+#[derive(GodotClass)]
+#[class(no_init, base=RefCounted)]
+pub struct Struct {
+    base: Base<RefCounted>,
+}
+
+#[godot_api]
+impl Struct {
+    /// Packs `values` into a `PackedByteArray`.
+    #[func]
+    fn pack(format: GString, values: VariantArray) -> Variant {
+        descriptor(&format)
+            .and_then(|descriptor| descriptor.pack(values).ok())
+            .map_or(Variant::nil(), |bytes| bytes.to_variant())
+    }
+
+    /// Unpacks `data`, which must hold exactly one record, into an `Array`.
+    #[func]
+    fn unpack(format: GString, data: PackedByteArray) -> Variant {
+        let Some(descriptor) = descriptor(&format) else {
+            return Variant::nil();
+        };
+        match unpack_at(&descriptor, data.as_slice(), 0) {
+            Some((values, size)) if size == data.len() => values.to_variant(),
+            Some((_, size)) => {
+                godot_error!(
+                    "Unpacking requires exactly {} bytes, got {}.",
+                    size,
+                    data.len()
+                );
+                Variant::nil()
+            }
+            None => Variant::nil(),
+        }
+    }
+
+    /// Returns the size of a record, the smallest one for variable layouts.
+    #[func]
+    fn calcsize(format: GString) -> i64 {
+        descriptor(&format).map_or(-1, |descriptor| descriptor.size() as i64)
+    }
+
+    /// Unpacks `data`, records back to back with nothing left over, into an `Array` of `Array`s.
+    #[func]
+    fn iter_unpack(format: GString, data: PackedByteArray) -> Variant {
+        let Some(descriptor) = descriptor(&format) else {
+            return Variant::nil();
+        };
+        let data = data.as_slice();
+        let mut records = VariantArray::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let Some((values, size)) = unpack_at(&descriptor, data, offset) else {
+                return Variant::nil();
+            };
+            if size == 0 {
+                godot_error!("Cannot iterate over empty records.");
+                return Variant::nil();
+            }
+            records.push(&values.to_variant());
+            offset += size;
+        }
+        records.to_variant()
+    }
+
+    /// Packs `values` into `buffer` at `offset`, negative offsets counting from its end. Packed arrays are
+    /// passed by value in GDScript, so the modified buffer is returned rather than changed in place.
+    #[func]
+    fn pack_into(
+        format: GString,
+        mut buffer: PackedByteArray,
+        offset: i64,
+        values: VariantArray,
+    ) -> Variant {
+        let Some(descriptor) = descriptor(&format) else {
+            return Variant::nil();
+        };
+        let Some(offset) = resolve_offset(offset, buffer.len()) else {
+            return Variant::nil();
+        };
+        let Ok(bytes) = descriptor.pack(values) else {
+            return Variant::nil();
+        };
+        let end = offset + bytes.len();
+        if end > buffer.len() {
+            godot_error!(
+                "Packing requires {} bytes at offset {}, the buffer holds {}.",
+                bytes.len(),
+                offset,
+                buffer.len()
+            );
+            return Variant::nil();
+        }
+        buffer.as_mut_slice()[offset..end].copy_from_slice(bytes.as_slice());
+        buffer.to_variant()
+    }
+
+    /// Unpacks a record of `data` at `offset`, negative offsets counting from its end, into an `Array`.
+    /// Bytes after the record are ignored.
+    #[func]
+    fn unpack_from(format: GString, data: PackedByteArray, offset: i64) -> Variant {
+        let Some(descriptor) = descriptor(&format) else {
+            return Variant::nil();
+        };
+        let Some(offset) = resolve_offset(offset, data.len()) else {
+            return Variant::nil();
+        };
+        unpack_at(&descriptor, data.as_slice(), offset)
+            .map_or(Variant::nil(), |(values, _)| values.to_variant())
+    }
+}